@@ -0,0 +1,300 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    domain::{
+        self,
+        nix::{LockedRev, LockedSource},
+        revision::{Revision, RevisionProvider},
+    },
+    fs::file_name_of,
+};
+
+/// How revisions are cached, in the directory of the mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Use cached revisions, and cache the ones looked up
+    Enabled(PathBuf),
+    /// Look up every revision again, and update the cache
+    Refresh(PathBuf),
+    /// Neither read nor write the cache
+    Disabled,
+}
+
+/// How long the refs containing a cached revision are trusted, new branches and tags may contain
+/// the revision later.
+const REFS_TTL: Duration = Duration::DAY;
+
+/// Caches the revisions found by another provider on disk, a revision's dates never change once
+/// it's found, the refs containing it are looked up again after [`REFS_TTL`]. Every revision is
+/// stored in its own file, that is replaced atomically, so concurrent runs never see a partially
+/// written entry.
+#[allow(clippy::module_name_repetitions)]
+pub struct CachedRevisionProvider<R: RevisionProvider> {
+    provider: R,
+    mode: CacheMode,
+}
+
+impl<R: RevisionProvider> CachedRevisionProvider<R> {
+    pub fn new(provider: R, mode: CacheMode) -> Self {
+        Self { provider, mode }
+    }
+
+    fn read(entry: &Path) -> Option<Entry> {
+        let json = fs::read_to_string(entry).ok()?;
+
+        serde_json::from_str(&json)
+            .map_err(|err| log::debug!("ignoring invalid cache entry {entry:?}: {err}"))
+            .ok()
+    }
+
+    fn write(entry: &Path, revision: &Revision) -> io::Result<()> {
+        let dir = entry.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+
+        let tmp = entry.with_extension(format!("json.{}.tmp", process::id()));
+        let json = serde_json::to_string(&Entry {
+            revision: revision.clone(),
+            refs_checked_at: OffsetDateTime::now_utc(),
+        })?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, entry).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+}
+
+impl<R: RevisionProvider> RevisionProvider for CachedRevisionProvider<R> {
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> domain::Result<Option<Revision>> {
+        let (entry, cached) = match &self.mode {
+            CacheMode::Enabled(dir) => {
+                let entry = entry(dir, source, rev);
+                let cached = Self::read(&entry);
+                (entry, cached)
+            }
+            CacheMode::Refresh(dir) => (entry(dir, source, rev), None),
+            CacheMode::Disabled => return self.provider.revision(source, rev),
+        };
+        if let Some(cached) = &cached {
+            if OffsetDateTime::now_utc() - cached.refs_checked_at < REFS_TTL {
+                log::debug!("found {} of {} in the cache", &**rev, source.repository());
+                return Ok(Some(cached.revision.clone()));
+            }
+        }
+
+        let revision = match (self.provider.revision(source, rev), cached) {
+            (Ok(Some(revision)), _) => Some(revision),
+            (Ok(None) | Err(_), Some(cached)) => {
+                log::debug!(
+                    "couldn't look up the refs of {} of {} again, they are left out",
+                    &**rev,
+                    source.repository()
+                );
+                return Ok(Some(Revision {
+                    refs: vec![],
+                    ..cached.revision
+                }));
+            }
+            (result, None) => result?,
+        };
+        if let Some(revision) = &revision {
+            if let Err(err) = Self::write(&entry, revision) {
+                log::warn!(
+                    "couldn't cache {} of {}: {err}",
+                    &**rev,
+                    source.repository()
+                );
+            }
+        }
+
+        Ok(revision)
+    }
+}
+
+/// The file the revision of the source is cached in.
+fn entry(dir: &Path, source: &LockedSource, rev: &LockedRev) -> PathBuf {
+    dir.join(file_name_of(&source.git_url()))
+        .join(format!("{}.json", file_name_of(rev)))
+}
+
+/// A cached revision, with the time the refs containing it were looked up.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    revision: Revision,
+    #[serde(with = "time::serde::rfc3339")]
+    refs_checked_at: OffsetDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use sealed_test::prelude::*;
+    use time::OffsetDateTime;
+
+    use crate::domain::{
+        self,
+        nix::{LockedRev, LockedSource},
+        revision::{fixtures::TestRevisionProvider, Revision, RevisionProvider},
+    };
+
+    use super::{entry, CacheMode, CachedRevisionProvider};
+
+    const REV: &str = "3892194d7b3293de8b30f1d19e2af45ba41ba8fd";
+
+    /// Counts the lookups that reach the wrapped provider
+    struct CountingProvider {
+        provider: TestRevisionProvider,
+        lookups: Cell<usize>,
+    }
+
+    impl RevisionProvider for &CountingProvider {
+        fn revision(
+            &self,
+            source: &LockedSource,
+            rev: &LockedRev,
+        ) -> domain::Result<Option<Revision>> {
+            self.lookups.set(self.lookups.get() + 1);
+            self.provider.revision(source, rev)
+        }
+    }
+
+    fn counting_provider() -> CountingProvider {
+        CountingProvider {
+            provider: TestRevisionProvider {
+                revisions: HashMap::from([(
+                    String::from(REV),
+                    Revision {
+                        commit_date: OffsetDateTime::UNIX_EPOCH,
+                        author_date: OffsetDateTime::UNIX_EPOCH,
+                        author: Some(String::from("voidcontext")),
+                        subject: Some(String::from("Release v0.3.0")),
+                        refs: vec![String::from("refs/tags/v0.3.0")],
+                    },
+                )]),
+                offline: false,
+            },
+            lookups: Cell::new(0),
+        }
+    }
+
+    fn source() -> LockedSource {
+        LockedSource::GitHub {
+            owner: String::from("voidcontext"),
+            repo: String::from("nix-rust-utils"),
+        }
+    }
+
+    /// Makes the refs of a cache entry older than the TTL
+    fn expire_refs(entry: &Path) {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(entry).unwrap()).unwrap();
+        json["refs_checked_at"] = serde_json::Value::from("2023-05-31T22:32:12Z");
+        fs::write(entry, json.to_string()).unwrap();
+    }
+
+    #[rstest]
+    #[case(CacheMode::Enabled(PathBuf::from("cache")), 1)]
+    #[case(CacheMode::Refresh(PathBuf::from("cache")), 2)]
+    #[case(CacheMode::Disabled, 2)]
+    #[sealed_test]
+    fn test_cached_revision_provider_looks_up_revisions_once(
+        #[case] mode: CacheMode,
+        #[case] expected_lookups: usize,
+    ) {
+        let provider = counting_provider();
+        let first = CachedRevisionProvider::new(&provider, mode.clone())
+            .revision(&source(), &LockedRev::from(REV))
+            .unwrap();
+        // a new provider, as if lamina was run again
+        let second = CachedRevisionProvider::new(&provider, mode.clone())
+            .revision(&source(), &LockedRev::from(REV))
+            .unwrap();
+
+        assert_eq!(provider.lookups.get(), expected_lookups);
+        assert_eq!(first, second);
+        assert_eq!(PathBuf::from("cache").exists(), mode != CacheMode::Disabled);
+    }
+
+    #[sealed_test]
+    fn test_cached_revision_provider_doesnt_cache_unknown_revisions() {
+        let provider = counting_provider();
+        let cache =
+            CachedRevisionProvider::new(&provider, CacheMode::Enabled(PathBuf::from("cache")));
+        let unknown = LockedRev::from("a08e061a4ee8329747d54ddf1566d34c55c895eb");
+
+        assert_eq!(cache.revision(&source(), &unknown).unwrap(), None);
+        assert_eq!(cache.revision(&source(), &unknown).unwrap(), None);
+        assert_eq!(provider.lookups.get(), 2);
+    }
+
+    #[sealed_test]
+    fn test_cached_revision_provider_looks_up_refs_again_after_ttl() {
+        let provider = counting_provider();
+        let cache =
+            CachedRevisionProvider::new(&provider, CacheMode::Enabled(PathBuf::from("cache")));
+        let rev = LockedRev::from(REV);
+        cache.revision(&source(), &rev).unwrap();
+
+        expire_refs(&entry(Path::new("cache"), &source(), &rev));
+
+        let revision = cache.revision(&source(), &rev).unwrap().unwrap();
+        cache.revision(&source(), &rev).unwrap();
+
+        assert_eq!(provider.lookups.get(), 2);
+        assert_eq!(revision.refs, vec!["refs/tags/v0.3.0"]);
+    }
+
+    #[sealed_test]
+    fn test_cached_revision_provider_keeps_revision_without_refs_when_offline() {
+        let mut provider = counting_provider();
+        let rev = LockedRev::from(REV);
+        let entry = {
+            let cache =
+                CachedRevisionProvider::new(&provider, CacheMode::Enabled(PathBuf::from("cache")));
+            cache.revision(&source(), &rev).unwrap();
+            entry(Path::new("cache"), &source(), &rev)
+        };
+        expire_refs(&entry);
+        provider.provider.offline = true;
+
+        let revision =
+            CachedRevisionProvider::new(&provider, CacheMode::Enabled(PathBuf::from("cache")))
+                .revision(&source(), &rev)
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(revision.author.as_deref(), Some("voidcontext"));
+        assert!(revision.refs.is_empty());
+    }
+
+    #[test]
+    fn test_cache_entries_of_different_sources_dont_collide() {
+        let rev = LockedRev::from(REV);
+        let entry = |repo: &str| {
+            entry(
+                Path::new("cache"),
+                &LockedSource::GitHub {
+                    owner: String::from("voidcontext"),
+                    repo: String::from(repo),
+                },
+                &rev,
+            )
+        };
+
+        assert_ne!(entry("nix-rust-utils"), entry("nix_rust_utils"));
+    }
+}
@@ -14,7 +14,9 @@ pub struct Args {
 pub enum Command {
     /// Syncs input with another flake
     Sync {
-        /// Path to the source flake
+        /// Path to the source flake, `PATH@REV` reads it at a git revision, e.g. platform@v1.2.0.
+        /// Flake references of git repositories are fetched, e.g. github:org/platform or
+        /// git+https://example.com/platform.git?ref=main
         src_flake: PathBuf,
         /// Name of the input in the source flake
         src_input_name: String,
@@ -23,16 +25,289 @@ pub enum Command {
         /// Name of the input in the destination flake, same as the SRC_INPUT_NAME if not provided.
         /// When this argument is set, DST_FLAKE needs to be set too.
         dst_input_name: Option<String>,
+        /// Treat DST_FLAKE as a directory tree and sync every flake found in it
+        #[clap(long, requires = "dst_flake")]
+        into_tree: bool,
+        /// Don't modify the destination, exit with status 2 if the input would change
+        #[clap(long)]
+        check: bool,
     },
     /// Syncs multiple inputs with another flake, inputs must have matching names
     BatchSync {
-        /// Path to the source flake
+        /// Path to the source flake, `PATH@REV` reads it at a git revision, e.g. platform@v1.2.0.
+        /// Flake references of git repositories are fetched, e.g. github:org/platform
         src_flake: PathBuf,
         /// Path to the destination flake
         dst_flake: PathBuf,
         /// Name of the inputs that will be synced
         inputs: Vec<String>,
+        /// Treat DST_FLAKE as a directory tree and sync every flake found in it that has any of
+        /// the inputs
+        #[clap(long)]
+        into_tree: bool,
+        /// Don't modify the destination, exit with status 2 if any of the inputs would change
+        #[clap(long)]
+        check: bool,
+    },
+    /// Compares the inputs of two flakes without modifying them
+    Status {
+        /// Path to the source flake, `PATH@REV` reads it at a git revision, e.g. platform@v1.2.0
+        src_flake: PathBuf,
+        /// Path to the destination flake, current dir if not provided. `PATH@REV` reads it at a
+        /// git revision
+        dst_flake: Option<PathBuf>,
+        /// Pair the inputs that don't have a matching name by their source repository
+        #[clap(long)]
+        by_source: bool,
+    },
+    /// Prints which revision of each input the given flakes are locked to
+    Matrix {
+        /// Paths to the flakes
+        flakes: Vec<PathBuf>,
+        /// Include every flake found in this directory tree
+        #[clap(long, value_name = "DIR")]
+        in_tree: Option<PathBuf>,
+        /// Print the matrix as JSON
+        #[clap(long)]
+        json: bool,
     },
     /// Prints the last modified date/time of the flake inputs
+    LastModified {
+        /// Path to the flake, current dir if not provided
+        #[clap(conflicts_with = "in_tree")]
+        flake: Option<PathBuf>,
+        /// Print the inputs of every flake found in this directory tree instead of the current
+        /// flake
+        #[clap(long, value_name = "DIR")]
+        in_tree: Option<PathBuf>,
+        /// Print every input of the lock graph as a tree under the input that brings it
+        #[clap(long, visible_alias = "tree")]
+        transitive: bool,
+        /// Output format
+        #[clap(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        /// Columns of the table, the other formats add the columns looked up from the revision
+        #[clap(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_values_t = [Column::Input, Column::LastModified]
+        )]
+        columns: Vec<Column>,
+        /// Order of the inputs
+        #[clap(long, value_enum, default_value_t = SortBy::Name)]
+        sort: SortBy,
+        /// Maximum age of the inputs (e.g. 30d), or of a single input by its path (e.g.
+        /// nixpkgs=14d or crane/nixpkgs=14d), exit with status 3 if any input is older
+        #[clap(long, value_name = "[INPUT=]AGE")]
+        max_age: Vec<String>,
+        /// Local git repository to look up commit dates in (can be repeated)
+        #[clap(long, value_name = "DIR")]
+        git_repo: Vec<PathBuf>,
+        /// Directory of the bare mirrors used to look up commit dates, defaults to
+        /// $XDG_CACHE_HOME/lamina/git
+        #[clap(long, value_name = "DIR")]
+        git_mirrors: Option<PathBuf>,
+        /// Clone or fetch the mirrors when a revision isn't found locally
+        #[clap(long)]
+        fetch_mirrors: bool,
+        /// URL of the GitHub API used to look up commit dates of GitHub inputs, authenticated
+        /// with GITHUB_TOKEN or the github.com entry of nix's access-tokens
+        #[clap(long, value_name = "URL", default_value = "https://api.github.com")]
+        github_api_url: String,
+        /// GitLab, Gitea or Forgejo instance hosting inputs, used to look up commit dates (can be
+        /// repeated), e.g. git.example.com=forgejo or git.example.com=gitlab:https://git.example.com/api/v4.
+        /// Tokens are read from LAMINA_FORGE_TOKENS or nix's access-tokens
+        #[clap(long, value_name = "HOST=KIND[:API_URL]")]
+        forge: Vec<String>,
+        /// Don't use the cache of the looked up revisions in $XDG_CACHE_HOME/lamina/revisions
+        #[clap(long, conflicts_with = "refresh")]
+        no_cache: bool,
+        /// Look up every revision again and update the cache
+        #[clap(long)]
+        refresh: bool,
+    },
+    /// Checks whether the refs tracked by the inputs moved since they were locked, and lists the
+    /// newer release tags of the inputs pinned to a tag
+    Outdated {
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+        /// Print the inputs as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Bumps the semver tag an input is pinned to in flake.nix, then relocks the input. Bumps to
+    /// the newest release by default
+    Bump {
+        /// The input to bump
+        input: String,
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+        /// Bump to the newest release with the same major version
+        #[clap(long, conflicts_with_all = ["patch", "to"])]
+        minor: bool,
+        /// Bump to the newest release with the same major and minor version
+        #[clap(long, conflicts_with_all = ["minor", "to"])]
+        patch: bool,
+        /// Bump to the newest release (default)
+        #[clap(long, conflicts_with_all = ["minor", "patch", "to"])]
+        major: bool,
+        /// Bump to the given tag
+        #[clap(long, value_name = "TAG")]
+        to: Option<String>,
+        /// Print the new tag and the tags in between without changing the flake
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Moves an input to the newest branch matching a pattern, e.g. from release-23.05 to
+    /// release-23.11, then relocks the input
+    BumpBranch {
+        /// The input to move
+        input: String,
+        /// Naming scheme of the branches, YYYY or YY stands for the year, MM for the month and DD
+        /// for the day
+        #[clap(long, value_name = "PATTERN")]
+        pattern: String,
+        /// Paths to the flakes, current dir if not provided
+        flakes: Vec<PathBuf>,
+        /// Include every flake found in this directory tree
+        #[clap(long, value_name = "DIR")]
+        in_tree: Option<PathBuf>,
+        /// Choose from these branches instead of listing the branches of the input's repository
+        #[clap(long, value_delimiter = ',')]
+        branches: Option<Vec<String>>,
+        /// Print the new branches without changing the flakes
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Shows the inputs that were added, removed or changed between two versions of a flake.lock
+    Diff {
+        /// The old flake.lock, or a range of git revisions of the flake, e.g. HEAD~1..HEAD.
+        /// `PATH@REV` reads the flake.lock at a git revision
+        old: String,
+        /// The new flake.lock, required unless a range of revisions is given. `PATH@REV` reads the
+        /// flake.lock at a git revision
+        new: Option<PathBuf>,
+        /// Path to the flake the revisions are read from, current dir if not provided
+        #[clap(long)]
+        flake: Option<PathBuf>,
+        /// Output format
+        #[clap(long, value_enum, default_value_t = DiffFormat::Table)]
+        format: DiffFormat,
+    },
+    /// Prints the node graph of the flake.lock, inputs following another input are drawn as
+    /// dashed edges
+    Graph {
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+        /// Output format
+        #[clap(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Merge the nodes of the same source into one node
+        #[clap(long)]
+        collapse: bool,
+        /// Highlight the nodes sharing their source with other nodes
+        #[clap(long)]
+        highlight: bool,
+    },
+    /// Lists the sources locked by more than one node, and the follows declarations that would
+    /// remove the copies
+    Dups {
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+        /// Print the duplicates as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Inserts follows declarations into flake.nix so that the inputs of inputs use one copy of
+    /// each source, then relocks the flake
+    Dedupe {
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+        /// Only dedupe this source, e.g. NixOS/nixpkgs
+        #[clap(long)]
+        source: Option<String>,
+        /// Print the follows declarations without changing the flake
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Explains why an input is locked at its revision: the paths bringing it, the inputs
+    /// following it and the overrides of flake.nix
+    Explain {
+        /// Path of the input in the lock graph, e.g. crane/nixpkgs
+        input: String,
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+    },
+    /// Checks that flake.lock matches flake.nix: declared inputs are locked, locked inputs are
+    /// declared, and urls and follows agree. Exits with 4 if they don't, e.g. in a pre-commit hook
+    Verify {
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+    },
+    /// Removes the nodes of flake.lock that aren't reachable from its root, e.g. the ones left
+    /// behind by manual edits or failed overrides
+    GcLock {
+        /// Lists the unreachable nodes without removing them, exits with 5 if there's any
+        #[clap(long)]
+        check: bool,
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+    },
+    /// Lists the inputs the outputs function of flake.nix doesn't use, exits with 6 if there's
+    /// any
+    UnusedInputs {
+        /// Removes the unused inputs from flake.nix and relocks the flake
+        #[clap(long)]
+        remove: bool,
+        /// Path to the flake, current dir if not provided
+        flake: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum DiffFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+    Markdown,
+    Tsv,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Column {
+    Input,
+    Type,
+    Source,
+    Ref,
+    Rev,
+    Pinned,
+    Age,
     LastModified,
+    CommitDate,
+    AuthorDate,
+    Author,
+    Subject,
+    Refs,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum SortBy {
+    Name,
+    Age,
+    Type,
 }
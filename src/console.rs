@@ -7,7 +7,9 @@ pub struct OsConsole {}
 
 impl domain::console::Console for OsConsole {
     fn println<S: AsRef<str>>(&self, s: S) -> domain::Result<()> {
-        io::stdout().write_all(s.as_ref().as_bytes())?;
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(s.as_ref().as_bytes())?;
+        stdout.write_all(b"\n")?;
         Ok(())
     }
 }
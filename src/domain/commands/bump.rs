@@ -0,0 +1,295 @@
+use std::path::Path;
+
+use semver::Version;
+
+use crate::domain::{
+    console::Console,
+    nix::{flake_url::with_ref, Flake, LockedSource},
+    remote::Remote,
+    Error, Result,
+};
+
+use super::tags::{newer_tags, tag_version};
+
+/// The tag an input pinned to a semver tag is bumped to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BumpTarget {
+    /// The newest release
+    Major,
+    /// The newest release with the same major version
+    Minor,
+    /// The newest release with the same major and minor version
+    Patch,
+    /// The given tag
+    To(String),
+}
+
+/// Bumps the tag the input is pinned to in flake.nix, then relocks the input. With `dry_run` only
+/// the change and the tags in between are printed.
+pub fn bump<F: Flake, R: Remote, C: Console>(
+    path: &Path,
+    input: &str,
+    target: &BumpTarget,
+    dry_run: bool,
+    flake: &F,
+    remote: &R,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = flake.load_lock_from(path)?;
+    let node = flake_lock
+        .input_nodes()
+        .remove(input)
+        .ok_or_else(|| Error::Error(format!("Couldn't find input {input}")))?;
+
+    let current_ref = node
+        .original
+        .r#ref
+        .as_ref()
+        .map(|r| String::from(&**r))
+        .filter(|r| tag_version(r).is_some())
+        .ok_or_else(|| Error::Error(format!("{input} isn't pinned to a semver tag")))?;
+    let current_tag = current_ref
+        .strip_prefix("refs/tags/")
+        .unwrap_or(&current_ref);
+    let current_version = tag_version(current_tag).unwrap_or_else(|| Version::new(0, 0, 0));
+
+    let refs = remote.refs(&node.locked.source.git_url())?;
+    let newer = newer_tags(current_tag, refs.tags());
+
+    let new_tag = match target {
+        BumpTarget::To(tag) => {
+            let tag = tag.strip_prefix("refs/tags/").unwrap_or(tag);
+            refs.tags()
+                .find(|t| *t == tag)
+                .ok_or_else(|| Error::Error(format!("Couldn't find tag {tag} of {input}")))?
+        }
+        target => match newer.iter().rfind(|tag| {
+            let version = tag_version(tag).unwrap_or_else(|| Version::new(0, 0, 0));
+            match target {
+                BumpTarget::Patch => {
+                    version.major == current_version.major && version.minor == current_version.minor
+                }
+                BumpTarget::Minor => version.major == current_version.major,
+                _ => true,
+            }
+        }) {
+            Some(tag) => tag,
+            None => {
+                return console
+                    .println(format!("{input}: {current_tag} is the newest matching tag"))
+            }
+        },
+    };
+
+    let new_ref = if current_ref.starts_with("refs/tags/") {
+        format!("refs/tags/{new_tag}")
+    } else {
+        String::from(new_tag)
+    };
+
+    let flake_nix = flake.load_from(path)?;
+    let url = flake_nix.input_url(input)?;
+    let new_url = with_ref(&url, &new_ref)?;
+
+    console.println(format!("{input}: {current_tag} -> {new_tag}"))?;
+    console.println(format!("  url: {url} -> {new_url}"))?;
+
+    let new_version = tag_version(new_tag).unwrap_or_else(|| Version::new(0, 0, 0));
+    let changes = newer
+        .iter()
+        .filter(|tag| tag_version(tag).is_some_and(|version| version <= new_version))
+        .copied()
+        .collect::<Vec<_>>();
+    if !changes.is_empty() {
+        console.println(format!("  changes: {}", changes.join(", ")))?;
+    }
+    if let Some(compare_url) = compare_url(&node.locked.source, current_tag, new_tag) {
+        console.println(format!("  compare: {compare_url}"))?;
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    flake.write(path, &flake_nix.with_input_url(input, &new_url)?)?;
+    flake.override_input(path, input, &new_url)
+}
+
+fn compare_url(source: &LockedSource, from: &str, to: &str) -> Option<String> {
+    match source {
+        LockedSource::GitHub { owner, repo } => Some(format!(
+            "https://github.com/{owner}/{repo}/compare/{from}...{to}"
+        )),
+        LockedSource::GitLab { owner, repo } => Some(format!(
+            "https://gitlab.com/{owner}/{repo}/-/compare/{from}...{to}"
+        )),
+        LockedSource::Git { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::{Path, PathBuf},
+    };
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{flake_lock_with_node, git_node_with_ref, TestFlake},
+            InputReference, LockedRev, OriginalRef,
+        },
+        remote::{fixtures::TestRemote, RemoteRefs},
+    };
+
+    use super::{bump, BumpTarget};
+
+    const URL: &str = "https://git.vdx.hu/voidcontext/nix-rust-utils.git";
+
+    fn flake_nix(r#ref: &str) -> String {
+        format!(
+            r#"{{
+  inputs.nix-rust-utils.url = "git+{URL}?ref={ref}";
+  outputs = {{...}}: {{}};
+}}"#
+        )
+    }
+
+    fn flake() -> TestFlake {
+        TestFlake::default()
+            .with_lock(
+                "flake",
+                flake_lock_with_node(
+                    "nix-rust-utils",
+                    git_node_with_ref(
+                        URL,
+                        &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+                        &OriginalRef::from("refs/tags/v0.3.0"),
+                    ),
+                ),
+            )
+            .with_flake_nix("flake", &flake_nix("refs/tags/v0.3.0"))
+    }
+
+    fn remote() -> TestRemote {
+        TestRemote {
+            repositories: HashMap::from([(
+                String::from(URL),
+                RemoteRefs::new(
+                    None,
+                    [
+                        "v0.2.0",
+                        "v0.3.0",
+                        "v0.3.1",
+                        "v0.4.0",
+                        "v1.0.0",
+                        "v1.1.0-rc.1",
+                    ]
+                    .into_iter()
+                    .map(|tag| {
+                        (
+                            format!("refs/tags/{tag}"),
+                            String::from("8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1"),
+                        )
+                    })
+                    .collect::<BTreeMap<_, _>>(),
+                ),
+            )]),
+        }
+    }
+
+    #[rstest]
+    #[case(BumpTarget::Patch, "v0.3.1")]
+    #[case(BumpTarget::Minor, "v0.4.0")]
+    #[case(BumpTarget::Major, "v1.0.0")]
+    #[case(BumpTarget::To(String::from("v0.2.0")), "v0.2.0")]
+    fn test_bump_rewrites_the_tag_in_flake_nix(#[case] target: BumpTarget, #[case] expected: &str) {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        bump(
+            Path::new("flake"),
+            "nix-rust-utils",
+            &target,
+            false,
+            &flake,
+            &remote(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            flake.flake_nixes.borrow()[&PathBuf::from("flake")],
+            flake_nix(&format!("refs/tags/{expected}"))
+        );
+        assert!(console
+            .output()
+            .starts_with(&format!("nix-rust-utils: v0.3.0 -> {expected}")));
+    }
+
+    #[test]
+    fn test_bump_dry_run_prints_the_changes_only() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        bump(
+            Path::new("flake"),
+            "nix-rust-utils",
+            &BumpTarget::Major,
+            true,
+            &flake,
+            &remote(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            flake.flake_nixes.borrow()[&PathBuf::from("flake")],
+            flake_nix("refs/tags/v0.3.0")
+        );
+        assert_eq!(
+            console.output(),
+            format!(
+                "nix-rust-utils: v0.3.0 -> v1.0.0
+  url: git+{URL}?ref=refs/tags/v0.3.0 -> git+{URL}?ref=refs/tags/v1.0.0
+  changes: v0.3.1, v0.4.0, v1.0.0"
+            )
+        );
+    }
+
+    #[test]
+    fn test_bump_fails_when_input_isnt_pinned_to_a_tag() {
+        let mut flake = flake();
+        flake
+            .flake_locks
+            .get_mut(&PathBuf::from("flake"))
+            .unwrap()
+            .root
+            .inputs
+            .insert(
+                String::from("nixpkgs"),
+                InputReference::Alias(String::from("nixpkgs")),
+            );
+
+        let result = bump(
+            Path::new("flake"),
+            "nixpkgs",
+            &BumpTarget::Major,
+            true,
+            &flake,
+            &remote(),
+            &TestConsole::default(),
+        );
+
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err(String::from(
+                "an error happened: \"nixpkgs isn't pinned to a semver tag\""
+            ))
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::{cell::RefCell, collections::HashMap, path::PathBuf};
+
+use crate::domain::{
+    console::Console,
+    nix::{flake_url::with_ref, Flake},
+    remote::{Remote, RemoteRefs},
+    Error, Result,
+};
+
+/// A branch naming scheme with date placeholders, e.g. `release-YY.MM` or `nixos-YYYY.MM`.
+/// `YYYY` and `YY` match the year, `MM` the month and `DD` the day, as digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchPattern {
+    parts: Vec<PatternPart>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatternPart {
+    Literal(char),
+    Year4,
+    Year2,
+    Month,
+    Day,
+}
+
+impl BranchPattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = pattern;
+
+        while let Some(c) = rest.chars().next() {
+            let (part, len) = if rest.starts_with("YYYY") {
+                (PatternPart::Year4, 4)
+            } else if rest.starts_with("YY") {
+                (PatternPart::Year2, 2)
+            } else if rest.starts_with("MM") {
+                (PatternPart::Month, 2)
+            } else if rest.starts_with("DD") {
+                (PatternPart::Day, 2)
+            } else {
+                (PatternPart::Literal(c), c.len_utf8())
+            };
+            parts.push(part);
+            rest = &rest[len..];
+        }
+
+        if parts
+            .iter()
+            .all(|part| matches!(part, PatternPart::Literal(_)))
+        {
+            return Err(Error::Error(format!(
+                "Branch pattern {pattern} has no YYYY, YY, MM or DD placeholder"
+            )));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// The date encoded in the branch name as `[year, month, day]`, `None` when the branch
+    /// doesn't match the pattern.
+    fn version_of(&self, branch: &str) -> Option<[u32; 3]> {
+        let mut version = [0; 3];
+        let mut rest = branch;
+
+        for part in &self.parts {
+            let (index, digits, offset) = match part {
+                PatternPart::Literal(c) => {
+                    rest = rest.strip_prefix(*c)?;
+                    continue;
+                }
+                PatternPart::Year4 => (0, 4, 0),
+                PatternPart::Year2 => (0, 2, 2000),
+                PatternPart::Month => (1, 2, 0),
+                PatternPart::Day => (2, 2, 0),
+            };
+
+            let value = rest.get(..digits)?;
+            if !value.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            version[index] = value.parse::<u32>().ok()? + offset;
+            rest = &rest[digits..];
+        }
+
+        rest.is_empty().then_some(version)
+    }
+
+    /// The newest of the branches matching the pattern.
+    fn newest<'a>(&self, branches: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+        branches
+            .filter_map(|branch| self.version_of(branch).map(|version| (version, branch)))
+            .max()
+            .map(|(_, branch)| branch)
+    }
+}
+
+/// Moves the input of every given flake to the newest branch matching `pattern`, then relocks
+/// it. The branches are listed from the input's repository, unless `branches` is given. Flakes
+/// without the input, or where the input doesn't track a branch matching the pattern, are
+/// skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn bump_branch<F: Flake, R: Remote, C: Console>(
+    flakes: &[PathBuf],
+    input: &str,
+    pattern: &BranchPattern,
+    branches: Option<&[String]>,
+    dry_run: bool,
+    flake: &F,
+    remote: &R,
+    console: &C,
+) -> Result<()> {
+    let remote_refs = RefCell::new(HashMap::<String, RemoteRefs>::new());
+    let list_branches = |url: String| -> Result<Vec<String>> {
+        if let Some(branches) = branches {
+            return Ok(branches.to_vec());
+        }
+
+        let mut remote_refs = remote_refs.borrow_mut();
+        if !remote_refs.contains_key(&url) {
+            let refs = remote.refs(&url)?;
+            remote_refs.insert(url.clone(), refs);
+        }
+
+        Ok(remote_refs[&url].branches().map(String::from).collect())
+    };
+
+    for path in flakes {
+        let flake_lock = flake.load_lock_from(path)?;
+        let Some(node) = flake_lock.input_nodes().remove(input) else {
+            log::debug!("skipping {}: {input} is missing", path.display());
+            continue;
+        };
+
+        let Some(current_branch) = node
+            .original
+            .r#ref
+            .as_ref()
+            .map(|r| String::from(&**r))
+            .filter(|r| pattern.version_of(r).is_some())
+        else {
+            log::warn!(
+                "skipping {}: {input} doesn't track a branch matching the pattern",
+                path.display()
+            );
+            continue;
+        };
+
+        let branches = list_branches(node.locked.source.git_url())?;
+        let newest = pattern
+            .newest(branches.iter().map(String::as_str))
+            .filter(|newest| pattern.version_of(newest) > pattern.version_of(&current_branch));
+
+        let Some(newest) = newest else {
+            console.println(format!(
+                "{}: {input}: {current_branch} is the newest matching branch",
+                path.display()
+            ))?;
+            continue;
+        };
+
+        let flake_nix = flake.load_from(path)?;
+        let url = flake_nix.input_url(input)?;
+        let new_url = with_ref(&url, newest)?;
+
+        console.println(format!(
+            "{}: {input}: {current_branch} -> {newest} ({new_url})",
+            path.display()
+        ))?;
+
+        if !dry_run {
+            flake.write(path, &flake_nix.with_input_url(input, &new_url)?)?;
+            flake.override_input(path, input, &new_url)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::PathBuf,
+    };
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{
+                flake_lock_with_node, github_node_with_ref, inputs, nixpkgs_node_with_ref,
+                TestFlake,
+            },
+            FlakeLock, LockedRev, Node, OriginalRef,
+        },
+        remote::{fixtures::TestRemote, RemoteRefs},
+    };
+
+    use super::{bump_branch, BranchPattern};
+
+    const REV: &str = "dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad";
+
+    #[rstest]
+    #[case("release-YY.MM", "release-23.05", Some([2023, 5, 0]))]
+    #[case("nixos-YYYY.MM", "nixos-2023.11", Some([2023, 11, 0]))]
+    #[case("release-YY.MM", "release-23.05-aarch64", None)]
+    #[case("release-YY.MM", "release-2x.05", None)]
+    #[case("release-YY.MM", "nixos-23.05", None)]
+    fn test_branch_pattern_matches_branches(
+        #[case] pattern: &str,
+        #[case] branch: &str,
+        #[case] expected: Option<[u32; 3]>,
+    ) {
+        assert_eq!(
+            BranchPattern::parse(pattern).unwrap().version_of(branch),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_branch_pattern_requires_a_placeholder() {
+        assert!(BranchPattern::parse("release").is_err());
+    }
+
+    fn flake_lock(node: Node) -> FlakeLock {
+        let mut flake_lock = flake_lock_with_node("nixpkgs-stable", node);
+        flake_lock.root.inputs = inputs("nixpkgs-stable");
+        flake_lock
+    }
+
+    fn flake() -> TestFlake {
+        TestFlake::default()
+            .with_lock(
+                "indirect",
+                flake_lock(nixpkgs_node_with_ref(
+                    &OriginalRef::from("release-23.05"),
+                    &LockedRev::from(REV),
+                )),
+            )
+            .with_flake_nix(
+                "indirect",
+                r#"{ inputs.nixpkgs-stable.url = "nixpkgs/release-23.05"; }"#,
+            )
+            .with_lock(
+                "github",
+                flake_lock(github_node_with_ref(
+                    "NixOS",
+                    "nixpkgs",
+                    &LockedRev::from(REV),
+                    &OriginalRef::from("release-23.11"),
+                )),
+            )
+            .with_flake_nix(
+                "github",
+                r#"{ inputs.nixpkgs-stable.url = "github:NixOS/nixpkgs/release-23.11"; }"#,
+            )
+            .with_lock(
+                "unstable",
+                flake_lock(github_node_with_ref(
+                    "NixOS",
+                    "nixpkgs",
+                    &LockedRev::from(REV),
+                    &OriginalRef::from("nixos-unstable"),
+                )),
+            )
+    }
+
+    #[test]
+    fn test_bump_branch_moves_every_flake_to_the_newest_branch() {
+        let console = TestConsole::default();
+        let flake = flake();
+        // the branches are given, the repositories aren't listed
+        let remote = TestRemote {
+            repositories: HashMap::new(),
+        };
+
+        bump_branch(
+            &[
+                PathBuf::from("indirect"),
+                PathBuf::from("github"),
+                PathBuf::from("unstable"),
+            ],
+            "nixpkgs-stable",
+            &BranchPattern::parse("release-YY.MM").unwrap(),
+            Some(&[String::from("release-23.11"), String::from("release-24.05")]),
+            false,
+            &flake,
+            &remote,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "indirect: nixpkgs-stable: release-23.05 -> release-24.05 (nixpkgs/release-24.05)
+github: nixpkgs-stable: release-23.11 -> release-24.05 (github:NixOS/nixpkgs/release-24.05)"
+        );
+        assert_eq!(
+            flake.flake_nixes.borrow()[&PathBuf::from("indirect")],
+            r#"{ inputs.nixpkgs-stable.url = "nixpkgs/release-24.05"; }"#
+        );
+        assert_eq!(
+            flake.flake_nixes.borrow()[&PathBuf::from("github")],
+            r#"{ inputs.nixpkgs-stable.url = "github:NixOS/nixpkgs/release-24.05"; }"#
+        );
+    }
+
+    #[test]
+    fn test_bump_branch_lists_branches_of_the_source() {
+        let console = TestConsole::default();
+        let remote = TestRemote {
+            repositories: HashMap::from([(
+                String::from("https://github.com/NixOS/nixpgks.git"),
+                RemoteRefs::new(
+                    None,
+                    BTreeMap::from([(String::from("refs/heads/release-23.11"), String::from(REV))]),
+                ),
+            )]),
+        };
+
+        bump_branch(
+            &[PathBuf::from("indirect")],
+            "nixpkgs-stable",
+            &BranchPattern::parse("release-YY.MM").unwrap(),
+            None,
+            true,
+            &flake(),
+            &remote,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "indirect: nixpkgs-stable: release-23.05 -> release-23.11 (nixpkgs/release-23.11)"
+        );
+    }
+}
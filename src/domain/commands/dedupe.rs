@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use crate::domain::{console::Console, nix::Flake, Result};
+
+use super::{dups::duplicates, format::count};
+
+/// Makes the copies of the sources locked more than once follow a single node by inserting the
+/// follows declarations [`super::dups`] suggests into flake.nix, then relocks the flake and
+/// reports how many nodes were removed from flake.lock. `source`, e.g. `NixOS/nixpkgs`, limits
+/// the deduplication to a single source.
+pub fn dedupe<F: Flake, C: Console>(
+    path: &Path,
+    source: Option<&str>,
+    dry_run: bool,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = flake.load_lock_from(path)?;
+    let duplicates = duplicates(&flake_lock)
+        .into_iter()
+        .filter(|duplicate| {
+            !duplicate.redirected_paths.is_empty()
+                && source.is_none_or(|source| matches_source(&duplicate.normalized_source, source))
+        })
+        .collect::<Vec<_>>();
+
+    if duplicates.is_empty() {
+        return console.println("nothing to dedupe");
+    }
+
+    let mut flake_nix = flake.load_from(path)?;
+    for duplicate in &duplicates {
+        for input_path in &duplicate.redirected_paths {
+            flake_nix = flake_nix.with_follows(input_path, &duplicate.keep)?;
+            console.println(format!(
+                "{}: follows {}",
+                input_path.join("/"),
+                duplicate.keep
+            ))?;
+        }
+    }
+
+    if dry_run {
+        let removable_nodes = duplicates
+            .iter()
+            .map(|duplicate| duplicate.removable_nodes)
+            .sum::<usize>();
+        return console.println(format!(
+            "would remove about {} from flake.lock",
+            count(removable_nodes, "node")
+        ));
+    }
+
+    flake.write(path, &flake_nix)?;
+    flake.lock(path)?;
+
+    let removed = flake_lock
+        .nodes
+        .len()
+        .saturating_sub(flake.load_lock_from(path)?.nodes.len());
+    console.println(format!(
+        "removed {} from flake.lock",
+        count(removed, "node")
+    ))
+}
+
+/// Whether the normalized source is the given source, e.g. `NixOS/nixpkgs` or
+/// `github:NixOS/nixpkgs`.
+fn matches_source(normalized_source: &str, source: &str) -> bool {
+    let source = source.to_lowercase();
+
+    normalized_source == source
+        || normalized_source
+            .split_once(':')
+            .is_some_and(|(_, repository)| repository == source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, github_node_with_ref, inputs, TestFlake},
+            Flake, FlakeLock, InputReference, LockedRev, OriginalRef,
+        },
+    };
+
+    use super::dedupe;
+
+    const FLAKE_NIX: &str = r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane/v0.16.0";
+  outputs = {...}: {};
+}"#;
+
+    /// The flake's nixpkgs, and an older copy of it `crane` brings with its own nixpkgs-lib
+    /// input. Once `crane` follows the flake's nixpkgs, both of the latter are gone.
+    fn flake_lock(deduped: bool) -> FlakeLock {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+
+        if deduped {
+            flake_lock.nodes.remove("nixpkgs_2");
+            flake_lock.nodes.get_mut("crane").unwrap().inputs.insert(
+                String::from("nixpkgs"),
+                InputReference::Path(vec![String::from("nixpkgs")]),
+            );
+        } else {
+            let nixpkgs_2 = flake_lock.nodes.get_mut("nixpkgs_2").unwrap();
+            nixpkgs_2.locked.last_modified -= Duration::days(30);
+            nixpkgs_2.inputs = inputs("nixpkgs-lib");
+            let mut nixpkgs_lib = github_node_with_ref(
+                "nix-community",
+                "nixpkgs.lib",
+                &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+                &OriginalRef::from("master"),
+            );
+            nixpkgs_lib.inputs.clear();
+            flake_lock
+                .nodes
+                .insert(String::from("nixpkgs-lib"), nixpkgs_lib);
+        }
+
+        flake_lock
+    }
+
+    fn flake() -> TestFlake {
+        TestFlake::default()
+            .with_lock("flake", flake_lock(false))
+            .with_relocked("flake", flake_lock(true))
+            .with_flake_nix("flake", FLAKE_NIX)
+    }
+
+    #[test]
+    fn test_dedupe_inserts_follows_and_relocks() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        dedupe(&PathBuf::from("flake"), None, false, &flake, &console).unwrap();
+
+        assert_eq!(
+            flake.load_from("flake").unwrap().as_string(),
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane/v0.16.0";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  outputs = {...}: {};
+}"#
+        );
+        assert_eq!(*flake.locked.borrow(), vec![PathBuf::from("flake")]);
+        assert_eq!(
+            console.output(),
+            "crane/nixpkgs: follows nixpkgs\nremoved 2 nodes from flake.lock"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_dry_run() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        dedupe(
+            &PathBuf::from("flake"),
+            Some("NixOS/nixpkgs"),
+            true,
+            &flake,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(flake.load_from("flake").unwrap().as_string(), FLAKE_NIX);
+        assert!(flake.locked.borrow().is_empty());
+        assert_eq!(
+            console.output(),
+            "crane/nixpkgs: follows nixpkgs\nwould remove about 2 nodes from flake.lock"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_other_source() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        dedupe(
+            &PathBuf::from("flake"),
+            Some("ipetkov/crane"),
+            false,
+            &flake,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(flake.load_from("flake").unwrap().as_string(), FLAKE_NIX);
+        assert_eq!(console.output(), "nothing to dedupe");
+    }
+}
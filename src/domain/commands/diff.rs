@@ -0,0 +1,369 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Color, Table};
+use serde::Serialize;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{FlakeLock, LockEdgeTarget},
+    Result,
+};
+
+use super::{
+    format::{markdown_cell, short_rev},
+    OutputFormat,
+};
+
+/// Prints the inputs that were added, removed or changed between two versions of a flake.lock.
+/// Inputs are paired by their path in the lock graph, e.g. `crane/nixpkgs`. Only the table, JSON
+/// and Markdown formats are supported.
+pub fn diff<C: Console>(
+    old: &FlakeLock,
+    new: &FlakeLock,
+    format: OutputFormat,
+    console: &C,
+) -> Result<()> {
+    let entries = entries(old, new);
+
+    match format {
+        OutputFormat::Table => console.println(format!("{}", table(&entries))),
+        OutputFormat::Json => console.println(
+            serde_json::to_string_pretty(&entries)
+                .map_err(|err| domain::Error::Error(err.to_string()))?,
+        ),
+        OutputFormat::Markdown => console.println(markdown(&entries)),
+        format => Err(domain::Error::Error(format!(
+            "{format:?} isn't supported by diff"
+        ))),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// An input as it is locked in one of the versions.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+struct Side {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    follows: Option<String>,
+    source: Option<String>,
+    r#ref: Option<String>,
+    rev: Option<String>,
+    last_modified: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Entry {
+    input: String,
+    change: Change,
+    old: Option<Side>,
+    new: Option<Side>,
+    /// The seconds the new revision was last modified after the old one
+    last_modified_delta: Option<i64>,
+}
+
+fn sides(flake_lock: &FlakeLock) -> BTreeMap<String, Side> {
+    flake_lock
+        .edges()
+        .into_iter()
+        .map(|edge| {
+            let side = match edge.target {
+                LockEdgeTarget::Node { node, .. } => Side {
+                    follows: None,
+                    source: Some(node.original.source.to_string()),
+                    r#ref: node
+                        .original
+                        .r#ref
+                        .as_ref()
+                        .map(|r| String::from(&**r))
+                        .or_else(|| node.locked.r#ref.as_ref().map(|r| String::from(&**r))),
+                    rev: Some(String::from(&*node.locked.rev)),
+                    last_modified: Some(node.locked.last_modified.unix_timestamp()),
+                },
+                LockEdgeTarget::Follows(path) => Side {
+                    follows: Some(path.join("/")),
+                    source: None,
+                    r#ref: None,
+                    rev: None,
+                    last_modified: None,
+                },
+            };
+            (edge.path.join("/"), side)
+        })
+        .collect()
+}
+
+fn entries(old: &FlakeLock, new: &FlakeLock) -> Vec<Entry> {
+    let mut old = sides(old);
+    let mut new = sides(new);
+    let inputs = old
+        .keys()
+        .chain(new.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    inputs
+        .into_iter()
+        .filter_map(|input| {
+            let old = old.remove(&input);
+            let new = new.remove(&input);
+            let change = match (&old, &new) {
+                (None, Some(_)) => Change::Added,
+                (Some(_), None) => Change::Removed,
+                (Some(old), Some(new)) if old != new => Change::Changed,
+                _ => return None,
+            };
+            let last_modified_delta = old
+                .as_ref()
+                .and_then(|old| old.last_modified)
+                .zip(new.as_ref().and_then(|new| new.last_modified))
+                .map(|(old, new)| new - old);
+
+            Some(Entry {
+                input,
+                change,
+                old,
+                new,
+                last_modified_delta,
+            })
+        })
+        .collect()
+}
+
+const HEADER: [&str; 6] = ["input", "change", "source", "ref", "rev", "last_modified"];
+
+fn cells(entry: &Entry) -> Vec<String> {
+    let field = |get: fn(&Side) -> Option<String>| {
+        let old = entry.old.as_ref().and_then(get);
+        let new = entry.new.as_ref().and_then(get);
+        match (old, new) {
+            (Some(old), Some(new)) if old != new => format!("{old} -> {new}"),
+            (Some(value), _) | (None, Some(value)) => value,
+            (None, None) => String::new(),
+        }
+    };
+
+    let last_modified = match entry.last_modified_delta {
+        Some(delta) => format_delta(delta),
+        None => String::new(),
+    };
+
+    vec![
+        entry.input.clone(),
+        String::from(match entry.change {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed => "changed",
+        }),
+        field(|side| {
+            side.follows
+                .as_ref()
+                .map(|follows| format!("follows {follows}"))
+                .or_else(|| side.source.clone())
+        }),
+        field(|side| side.r#ref.clone()),
+        field(|side| side.rev.as_deref().map(short_rev).map(String::from)),
+        last_modified,
+    ]
+}
+
+/// Formats the difference of two timestamps, e.g. "+23 days".
+fn format_delta(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "+" };
+    let seconds = seconds.abs();
+
+    let (amount, unit) = if seconds >= 86400 {
+        (seconds / 86400, "day")
+    } else if seconds >= 3600 {
+        (seconds / 3600, "hour")
+    } else {
+        (seconds / 60, "minute")
+    };
+
+    if amount == 1 {
+        format!("{sign}{amount} {unit}")
+    } else {
+        format!("{sign}{amount} {unit}s")
+    }
+}
+
+fn table(entries: &[Entry]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(HEADER);
+
+    for entry in entries {
+        let color = match entry.change {
+            Change::Added => Color::Green,
+            Change::Removed => Color::Red,
+            Change::Changed => Color::Yellow,
+        };
+        table.add_row(
+            cells(entry)
+                .into_iter()
+                .map(|content| Cell::new(content).fg(color))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    table
+}
+
+fn markdown(entries: &[Entry]) -> String {
+    let mut lines = vec![
+        format!("| {} |", HEADER.join(" | ")),
+        format!("|{}", "---|".repeat(HEADER.len())),
+    ];
+    lines.extend(entries.iter().map(|entry| {
+        let cells = cells(entry)
+            .iter()
+            .map(|cell| markdown_cell(cell))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    }));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    use crate::domain::{
+        commands::OutputFormat,
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{flake_lock_with_node, github_node_with_ref},
+            FlakeLock, InputReference, LockedRev, OriginalRef,
+        },
+    };
+
+    use super::diff;
+
+    fn flake_lock(rev: &str, r#ref: &str) -> FlakeLock {
+        flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_ref(
+                "voidcontext",
+                "nix-rust-utils",
+                &LockedRev::from(rev),
+                &OriginalRef::from(r#ref),
+            ),
+        )
+    }
+
+    fn old_and_new() -> (FlakeLock, FlakeLock) {
+        let old = flake_lock(
+            "3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+            "refs/tags/v0.3.0",
+        );
+        let mut new = flake_lock(
+            "8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1",
+            "refs/tags/v0.4.0",
+        );
+        let node = new.nodes.get_mut("nix-rust-utils").unwrap();
+        node.locked.last_modified += Duration::days(23);
+        node.inputs.insert(
+            String::from("flake-utils"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+        new.root.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+
+        (old, new)
+    }
+
+    #[test]
+    fn test_diff_prints_markdown() {
+        let console = TestConsole::default();
+        let (old, new) = old_and_new();
+
+        diff(&old, &new, OutputFormat::Markdown, &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            "| input | change | source | ref | rev | last_modified |
+|---|---|---|---|---|---|
+| nix-rust-utils | changed | github:voidcontext/nix-rust-utils | refs/tags/v0.3.0 -> refs/tags/v0.4.0 | 3892194 -> 8b3ad2f | +23 days |
+| nix-rust-utils/flake-utils | added | follows nixpkgs |  |  |  |
+| nixpkgs | added | nixpkgs |  | a08e061 |  |"
+        );
+    }
+
+    #[test]
+    fn test_diff_prints_json() {
+        let console = TestConsole::default();
+        let (old, new) = old_and_new();
+
+        diff(&new, &old, OutputFormat::Json, &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            r#"[
+  {
+    "input": "nix-rust-utils",
+    "change": "changed",
+    "old": {
+      "source": "github:voidcontext/nix-rust-utils",
+      "ref": "refs/tags/v0.4.0",
+      "rev": "8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1",
+      "last_modified": 1687559532
+    },
+    "new": {
+      "source": "github:voidcontext/nix-rust-utils",
+      "ref": "refs/tags/v0.3.0",
+      "rev": "3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+      "last_modified": 1685572332
+    },
+    "last_modified_delta": -1987200
+  },
+  {
+    "input": "nix-rust-utils/flake-utils",
+    "change": "removed",
+    "old": {
+      "follows": "nixpkgs",
+      "source": null,
+      "ref": null,
+      "rev": null,
+      "last_modified": null
+    },
+    "new": null,
+    "last_modified_delta": null
+  },
+  {
+    "input": "nixpkgs",
+    "change": "removed",
+    "old": {
+      "source": "nixpkgs",
+      "ref": null,
+      "rev": "a08e061a4ee8329747d54ddf1566d34c55c895eb",
+      "last_modified": 1683627095
+    },
+    "new": null,
+    "last_modified_delta": null
+  }
+]"#
+        );
+    }
+
+    #[test]
+    fn test_diff_prints_nothing_changed() {
+        let console = TestConsole::default();
+        let (old, _) = old_and_new();
+
+        diff(&old, &old, OutputFormat::Markdown, &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            "| input | change | source | ref | rev | last_modified |\n|---|---|---|---|---|---|"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Table};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock, Node},
+    Result,
+};
+
+use super::format::{count, short_rev};
+
+/// Lists the sources that are locked by more than one node, e.g. the copies of nixpkgs the
+/// inputs bring, with the input paths that bring each revision. Also suggests the follows
+/// declarations that would make the copies use one node, and how many nodes they would remove
+/// from the lock.
+pub fn dups<F: Flake, C: Console>(
+    path: Option<&Path>,
+    json: bool,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = match path {
+        Some(path) => flake.load_lock_from(path)?,
+        None => flake.load_lock()?,
+    };
+    let duplicates = duplicates(&flake_lock);
+
+    if json {
+        console.println(
+            serde_json::to_string_pretty(&duplicates)
+                .map_err(|err| domain::Error::Error(err.to_string()))?,
+        )
+    } else if duplicates.is_empty() {
+        console.println("every source is locked once")
+    } else {
+        console.println(format!("{}", table(&duplicates)))?;
+
+        for duplicate in &duplicates {
+            console.println(summary(duplicate))?;
+            for follows in &duplicate.follows {
+                console.println(format!("  {follows}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct Duplicate {
+    pub(super) source: String,
+    #[serde(skip)]
+    pub(super) normalized_source: String,
+    revisions: Vec<DuplicateRevision>,
+    /// The input path of the node the other copies could follow
+    pub(super) keep: String,
+    /// The input paths of the copies that could follow `keep`
+    #[serde(skip)]
+    pub(super) redirected_paths: Vec<Vec<String>>,
+    /// The follows declarations of flake.nix that would make the other copies use `keep`
+    follows: Vec<String>,
+    /// The number of nodes the follows declarations would remove from the lock
+    pub(super) removable_nodes: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateRevision {
+    rev: String,
+    #[serde(with = "time::serde::rfc3339")]
+    last_modified: OffsetDateTime,
+    nodes: Vec<String>,
+    paths: Vec<String>,
+}
+
+pub(super) fn duplicates(flake_lock: &FlakeLock) -> Vec<Duplicate> {
+    let node_paths = flake_lock.node_paths();
+
+    let mut nodes_by_source = BTreeMap::<String, Vec<(&str, &Node)>>::new();
+    for (name, node) in &flake_lock.nodes {
+        // nodes that no input brings are left for gc-lock
+        if node_paths.contains_key(name.as_str()) {
+            nodes_by_source
+                .entry(node.locked.source.normalized())
+                .or_default()
+                .push((name, node));
+        }
+    }
+
+    nodes_by_source
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(normalized_source, mut group)| {
+            group.sort_by_key(|(name, _)| *name);
+            let shortest_path = |name: &str| {
+                node_paths[name]
+                    .iter()
+                    .min_by_key(|path| path.len())
+                    .cloned()
+                    .unwrap_or_default()
+            };
+
+            // the copy closest to the root is kept, the newest one if there are more of them
+            let (keep, _) = *group
+                .iter()
+                .min_by_key(|(name, node)| {
+                    (
+                        shortest_path(name).len(),
+                        Reverse(node.locked.last_modified),
+                    )
+                })
+                .expect("groups have more than one node");
+            let keep_path = shortest_path(keep).join("/");
+
+            // inputs of the flake itself are deliberately separate, e.g. nixpkgs-stable
+            let redirected = group
+                .iter()
+                .map(|(name, _)| *name)
+                .filter(|name| *name != keep && node_paths[name].iter().all(|path| path.len() > 1))
+                .collect::<HashSet<_>>();
+            let redirected_paths = redirected
+                .iter()
+                .flat_map(|name| node_paths[name].iter().cloned())
+                .collect::<BTreeSet<_>>();
+            let follows = redirected_paths
+                .iter()
+                .map(|path| {
+                    format!(
+                        "inputs.{}.follows = \"{keep_path}\";",
+                        path.join(".inputs.")
+                    )
+                })
+                .collect();
+            let removable_nodes = flake_lock.reachable(&HashMap::new()).len()
+                - flake_lock
+                    .reachable(&redirected.iter().map(|name| (*name, keep)).collect())
+                    .len();
+
+            let mut revisions = BTreeMap::<&str, DuplicateRevision>::new();
+            for (name, node) in &group {
+                let revision =
+                    revisions
+                        .entry(&node.locked.rev)
+                        .or_insert_with(|| DuplicateRevision {
+                            rev: String::from(&*node.locked.rev),
+                            last_modified: node.locked.last_modified,
+                            nodes: vec![],
+                            paths: vec![],
+                        });
+                revision.nodes.push(String::from(*name));
+                revision
+                    .paths
+                    .extend(node_paths[name].iter().map(|path| path.join("/")));
+            }
+            let mut revisions = revisions.into_values().collect::<Vec<_>>();
+            revisions.sort_by_key(|revision| Reverse(revision.last_modified));
+
+            Duplicate {
+                source: group[0].1.locked.source.to_string(),
+                normalized_source,
+                revisions,
+                keep: keep_path,
+                redirected_paths: redirected_paths.into_iter().collect(),
+                follows,
+                removable_nodes,
+            }
+        })
+        .collect()
+}
+
+fn table(duplicates: &[Duplicate]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(vec!["source", "rev", "last modified", "input paths"]);
+
+    for duplicate in duplicates {
+        for (index, revision) in duplicate.revisions.iter().enumerate() {
+            table.add_row(vec![
+                Cell::new(if index == 0 {
+                    duplicate.source.as_str()
+                } else {
+                    ""
+                }),
+                Cell::new(short_rev(&revision.rev)),
+                Cell::new(revision.last_modified.date()),
+                Cell::new(revision.paths.join(", ")),
+            ]);
+        }
+    }
+
+    table
+}
+
+fn summary(duplicate: &Duplicate) -> String {
+    let nodes = duplicate
+        .revisions
+        .iter()
+        .map(|revision| revision.nodes.len())
+        .sum::<usize>();
+    let locked = format!(
+        "{}: {} revisions in {nodes} nodes",
+        duplicate.source,
+        duplicate.revisions.len()
+    );
+
+    if duplicate.follows.is_empty() {
+        format!("{locked}, every copy is an input of the flake")
+    } else {
+        format!(
+            "{locked}, following {} would remove {}:",
+            duplicate.keep,
+            count(duplicate.removable_nodes, "node")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, github_node_with_ref, inputs, TestFlake},
+            FlakeLock, InputReference, LockedRev, OriginalRef,
+        },
+    };
+
+    use super::dups;
+
+    /// The flake's nixpkgs, and an older copy of it `crane` brings with its own nixpkgs-lib input.
+    /// `rust-overlay` already follows the flake's nixpkgs.
+    fn flake_lock() -> FlakeLock {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.nodes.get_mut("crane").unwrap().inputs.insert(
+            String::from("rust-overlay"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+
+        let nixpkgs_2 = flake_lock.nodes.get_mut("nixpkgs_2").unwrap();
+        nixpkgs_2.locked.last_modified -= Duration::days(30);
+        nixpkgs_2.inputs = inputs("nixpkgs-lib");
+        let mut nixpkgs_lib = github_node_with_ref(
+            "nix-community",
+            "nixpkgs.lib",
+            &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+            &OriginalRef::from("master"),
+        );
+        nixpkgs_lib.inputs.clear();
+        flake_lock
+            .nodes
+            .insert(String::from("nixpkgs-lib"), nixpkgs_lib);
+
+        flake_lock
+    }
+
+    fn print(flake_lock: FlakeLock, json: bool) -> String {
+        let console = TestConsole::default();
+        let flake = TestFlake::default().with_lock(PathBuf::from("flake"), flake_lock);
+
+        dups(Some(&PathBuf::from("flake")), json, &flake, &console).unwrap();
+
+        console.output()
+    }
+
+    #[test]
+    fn test_dups_prints_duplicates_and_follows() {
+        assert_eq!(
+            print(flake_lock(), false),
+            r#"┌────────────────────────────────────────────────────────────────┐
+│ source                 rev       last modified   input paths   │
+╞════════════════════════════════════════════════════════════════╡
+│ github:NixOS/nixpkgs   a08e061   2023-05-09      nixpkgs       │
+│                        dc1517e   2023-04-09      crane/nixpkgs │
+└────────────────────────────────────────────────────────────────┘
+github:NixOS/nixpkgs: 2 revisions in 2 nodes, following nixpkgs would remove 2 nodes:
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";"#
+        );
+    }
+
+    #[test]
+    fn test_dups_prints_json() {
+        assert_eq!(
+            print(flake_lock(), true),
+            r#"[
+  {
+    "source": "github:NixOS/nixpkgs",
+    "revisions": [
+      {
+        "rev": "a08e061a4ee8329747d54ddf1566d34c55c895eb",
+        "last_modified": "2023-05-09T10:11:35Z",
+        "nodes": [
+          "nixpkgs"
+        ],
+        "paths": [
+          "nixpkgs"
+        ]
+      },
+      {
+        "rev": "dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "last_modified": "2023-04-09T10:11:35Z",
+        "nodes": [
+          "nixpkgs_2"
+        ],
+        "paths": [
+          "crane/nixpkgs"
+        ]
+      }
+    ],
+    "keep": "nixpkgs",
+    "follows": [
+      "inputs.crane.inputs.nixpkgs.follows = \"nixpkgs\";"
+    ],
+    "removable_nodes": 2
+  }
+]"#
+        );
+    }
+
+    #[test]
+    fn test_dups_without_duplicates() {
+        let mut flake_lock = flake_lock();
+        flake_lock.root.inputs = inputs("nixpkgs");
+
+        assert_eq!(print(flake_lock, false), "every source is locked once");
+    }
+}
@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock, FlakeNix, LockEdgeTarget},
+    Result,
+};
+
+use super::format::{original, short_rev};
+
+/// Explains why the input at `input`, e.g. `crane/nixpkgs`, is locked at its revision: the node
+/// locking it, what declared it, every input path that brings the node, the inputs following it,
+/// and the declarations of flake.nix overriding any of these inputs.
+pub fn explain<F: Flake, C: Console>(
+    path: &Path,
+    input: &str,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = flake.load_lock_from(path)?;
+    let flake_nix = flake
+        .load_from(path)
+        .map_err(|err| log::debug!("overrides are not shown, couldn't load flake.nix: {err}"))
+        .ok();
+
+    let input_path = input.split('/').map(String::from).collect::<Vec<_>>();
+    let node_name = flake_lock
+        .resolve_path(&input_path)
+        .ok_or_else(|| domain::Error::Error(format!("{input} is not in flake.lock")))?;
+    let node = flake_lock.nodes.get(&node_name).ok_or_else(|| {
+        domain::Error::Error(format!(
+            "{input} is locked by node {node_name}, which is not in flake.lock"
+        ))
+    })?;
+
+    let mut lines = vec![
+        format!("{input} is locked by node {node_name}"),
+        format!("  declared as: {}", original(&node.original)),
+        format!(
+            "  locked at:   {} {} (last modified {})",
+            node.locked.source,
+            short_rev(&node.locked.rev),
+            node.locked.last_modified.date()
+        ),
+    ];
+
+    let mut brought_by = Vec::new();
+    let mut followed_by = Vec::new();
+    for edge in flake_lock.edges() {
+        match edge.target {
+            LockEdgeTarget::Node {
+                node_name: name, ..
+            } if name == node_name => {
+                brought_by.push(edge.path);
+            }
+            LockEdgeTarget::Follows(follows)
+                if flake_lock.resolve_path(follows).as_ref() == Some(&node_name) =>
+            {
+                followed_by.push((edge.path, follows.join("/")));
+            }
+            _ => {}
+        }
+    }
+
+    lines.push(String::from("brought by:"));
+    lines.extend(
+        brought_by
+            .iter()
+            .map(|path| format!("  {} ({})", path.join("/"), chain(&flake_lock, path))),
+    );
+
+    if !followed_by.is_empty() {
+        lines.push(String::from("followed by:"));
+        lines.extend(
+            followed_by
+                .iter()
+                .map(|(path, follows)| format!("  {} follows {follows}", path.join("/"))),
+        );
+    }
+
+    if let Some(flake_nix) = &flake_nix {
+        let overrides = brought_by
+            .iter()
+            .chain(followed_by.iter().map(|(path, _)| path))
+            .flat_map(|path| overrides(flake_nix, path))
+            .collect::<Vec<_>>();
+
+        if overrides.is_empty() {
+            lines.push(String::from(
+                "not overridden by flake.nix, the inputs bringing it declare it",
+            ));
+        } else {
+            lines.push(String::from("overridden by flake.nix:"));
+            lines.extend(overrides.into_iter().map(|line| format!("  {line}")));
+        }
+    }
+
+    console.println(lines.join("\n"))
+}
+
+/// The nodes along the input path, e.g. `root -> crane -> nixpkgs_2`.
+fn chain(flake_lock: &FlakeLock, path: &[String]) -> String {
+    let mut nodes = vec![String::from("root")];
+    nodes.extend((1..=path.len()).filter_map(|length| flake_lock.resolve_path(&path[..length])));
+    nodes.join(" -> ")
+}
+
+/// The declarations of flake.nix setting the url or follows of an input of an input.
+fn overrides(flake_nix: &FlakeNix, path: &[String]) -> Vec<String> {
+    if path.len() < 2 {
+        return vec![];
+    }
+
+    ["url", "follows"]
+        .into_iter()
+        .filter_map(|attr| {
+            flake_nix
+                .nested_input_attr(path, attr)
+                .map(|value| format!("inputs.{}.{attr} = \"{value}\";", path.join(".inputs.")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, github_node_with_ref, TestFlake},
+            InputReference, LockedRev, OriginalRef,
+        },
+    };
+
+    use super::explain;
+
+    /// `crane` brings its own nixpkgs that `rust-overlay` follows, flake.nix overrides the url
+    /// of crane's nixpkgs.
+    fn flake() -> TestFlake {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.root.inputs.insert(
+            String::from("rust-overlay"),
+            InputReference::Alias(String::from("rust-overlay")),
+        );
+
+        let mut nixpkgs_2 = github_node_with_ref(
+            "NixOS",
+            "nixpkgs",
+            &LockedRev::from("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad"),
+            &OriginalRef::from("nixos-unstable"),
+        );
+        nixpkgs_2.inputs.clear();
+        flake_lock
+            .nodes
+            .insert(String::from("nixpkgs_2"), nixpkgs_2);
+        let mut rust_overlay = github_node_with_ref(
+            "oxalica",
+            "rust-overlay",
+            &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+            &OriginalRef::from("master"),
+        );
+        rust_overlay.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Path(vec![String::from("crane"), String::from("nixpkgs")]),
+        );
+        flake_lock
+            .nodes
+            .insert(String::from("rust-overlay"), rust_overlay);
+
+        TestFlake::default()
+            .with_lock("flake", flake_lock)
+            .with_flake_nix(
+                "flake",
+                r#"{
+  inputs.nixpkgs.url = "nixpkgs";
+  inputs.crane.url = "github:ipetkov/crane/v0.16.0";
+  inputs.crane.inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+  inputs.rust-overlay.url = "github:oxalica/rust-overlay";
+  inputs.rust-overlay.inputs.nixpkgs.follows = "crane/nixpkgs";
+}"#,
+            )
+    }
+
+    #[test]
+    fn test_explain_prints_provenance() {
+        let console = TestConsole::default();
+
+        explain(
+            &PathBuf::from("flake"),
+            "rust-overlay/nixpkgs",
+            &flake(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            r#"rust-overlay/nixpkgs is locked by node nixpkgs_2
+  declared as: github:NixOS/nixpkgs (ref nixos-unstable)
+  locked at:   github:NixOS/nixpkgs dc1517e (last modified 2023-05-31)
+brought by:
+  crane/nixpkgs (root -> crane -> nixpkgs_2)
+followed by:
+  rust-overlay/nixpkgs follows crane/nixpkgs
+overridden by flake.nix:
+  inputs.crane.inputs.nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+  inputs.rust-overlay.inputs.nixpkgs.follows = "crane/nixpkgs";"#
+        );
+    }
+
+    #[test]
+    fn test_explain_unknown_input() {
+        let console = TestConsole::default();
+
+        let result = explain(
+            &PathBuf::from("flake"),
+            "crane/flake-utils",
+            &flake(),
+            &console,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_explain_input_of_missing_node() {
+        let console = TestConsole::default();
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.nodes.remove("nixpkgs_2");
+        let flake = TestFlake::default().with_lock("flake", flake_lock);
+
+        let result = explain(&PathBuf::from("flake"), "crane/nixpkgs", &flake, &console);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use time::{Duration, OffsetDateTime};
+
+use crate::domain::nix::Original;
+
+const SHORT_REV_LENGTH: usize = 7;
+
+#[must_use]
+pub(crate) fn short_rev(rev: &str) -> &str {
+    rev.get(..SHORT_REV_LENGTH).unwrap_or(rev)
+}
+
+/// Formats a count of things, e.g. "1 node" or "3 nodes".
+#[must_use]
+pub(crate) fn count(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// Formats how flake.nix referred to a source, e.g. "github:NixOS/nixpkgs (ref nixos-unstable)".
+#[must_use]
+pub(crate) fn original(original: &Original) -> String {
+    match (&original.r#ref, &original.rev) {
+        (Some(r#ref), Some(rev)) => {
+            format!("{} (ref {}, rev {})", original.source, &**r#ref, &**rev)
+        }
+        (Some(r#ref), None) => format!("{} (ref {})", original.source, &**r#ref),
+        (None, Some(rev)) => format!("{} (rev {})", original.source, &**rev),
+        (None, None) => original.source.to_string(),
+    }
+}
+
+/// Escapes a field of a TSV row: backslashes, tabs and line breaks are written as `\\`, `\t`,
+/// `\n` and `\r`.
+#[must_use]
+pub(crate) fn tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Escapes a cell of a Markdown table, pipes would end the cell and line breaks the row.
+#[must_use]
+pub(crate) fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\n', '\r'], " ")
+}
+
+/// Formats the time elapsed since `then` in a human readable way, e.g. "23 days ago".
+#[must_use]
+pub(crate) fn relative_age(now: OffsetDateTime, then: OffsetDateTime) -> String {
+    let age = now - then;
+
+    if age < Duration::ZERO {
+        return String::from("in the future");
+    }
+
+    let (amount, unit) = if age.whole_days() >= 365 {
+        (age.whole_days() / 365, "year")
+    } else if age.whole_days() >= 30 {
+        (age.whole_days() / 30, "month")
+    } else if age.whole_days() >= 1 {
+        (age.whole_days(), "day")
+    } else if age.whole_hours() >= 1 {
+        (age.whole_hours(), "hour")
+    } else if age.whole_minutes() >= 1 {
+        (age.whole_minutes(), "minute")
+    } else {
+        return String::from("just now");
+    };
+
+    if amount == 1 {
+        format!("{amount} {unit} ago")
+    } else {
+        format!("{amount} {unit}s ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{count, markdown_cell, relative_age, short_rev, tsv_field};
+
+    #[rstest]
+    #[case(Duration::seconds(20), "just now")]
+    #[case(Duration::minutes(1), "1 minute ago")]
+    #[case(Duration::hours(5), "5 hours ago")]
+    #[case(Duration::days(23), "23 days ago")]
+    #[case(Duration::days(65), "2 months ago")]
+    #[case(Duration::days(800), "2 years ago")]
+    #[case(Duration::days(-1), "in the future")]
+    fn test_relative_age(#[case] age: Duration, #[case] expected: &str) {
+        #[allow(clippy::unreadable_literal)]
+        let now = OffsetDateTime::from_unix_timestamp(1685572332).unwrap();
+
+        assert_eq!(relative_age(now, now - age), expected);
+    }
+
+    #[test]
+    fn test_short_rev() {
+        assert_eq!(
+            short_rev("a08e061a4ee8329747d54ddf1566d34c55c895eb"),
+            "a08e061"
+        );
+        assert_eq!(short_rev("a08e"), "a08e");
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(1, "node"), "1 node");
+        assert_eq!(count(3, "node"), "3 nodes");
+    }
+
+    #[test]
+    fn test_tsv_field() {
+        assert_eq!(
+            tsv_field("fix\tthings\nand\\more"),
+            r"fix\tthings\nand\\more"
+        );
+    }
+
+    #[test]
+    fn test_markdown_cell() {
+        assert_eq!(markdown_cell("a | b\nc"), r"a \| b c");
+    }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::domain::{self, console::Console, nix::Flake, Result};
+
+use super::format::{count, short_rev};
+
+/// Removes the nodes of flake.lock that aren't reachable from its root, e.g. the ones left behind
+/// by manual edits or failed overrides, and lists them. When `check` is set flake.lock is left
+/// untouched, and the command fails with [`domain::Error::UnreachableNodes`] if there's any.
+pub fn gc_lock<F: Flake, C: Console>(
+    path: &Path,
+    check: bool,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = flake.load_lock_from(path)?;
+    let reachable = flake_lock.reachable(&HashMap::new());
+
+    let mut unreachable = flake_lock
+        .nodes
+        .iter()
+        .filter(|(name, _)| !reachable.contains(*name))
+        .collect::<Vec<_>>();
+    unreachable.sort_by_key(|(name, _)| *name);
+
+    if unreachable.is_empty() {
+        return console.println("every node is reachable from the root");
+    }
+
+    let header = if check {
+        format!(
+            "{} unreachable from the root of flake.lock:",
+            count(unreachable.len(), "node")
+        )
+    } else {
+        format!(
+            "removed {} from flake.lock:",
+            count(unreachable.len(), "node")
+        )
+    };
+    console.println(header)?;
+    for (name, node) in &unreachable {
+        console.println(format!(
+            "  {name} ({} {})",
+            node.locked.source,
+            short_rev(&node.locked.rev)
+        ))?;
+    }
+
+    let names = unreachable
+        .into_iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    if check {
+        Err(domain::Error::UnreachableNodes(names))
+    } else {
+        flake.remove_lock_nodes(path, &names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, github_node_with_ref, inputs, TestFlake},
+            Flake, InputReference, LockedRev, OriginalRef,
+        },
+    };
+
+    use super::gc_lock;
+
+    /// `crane` follows the flake's nixpkgs, its former nixpkgs and the flake-utils of that are
+    /// left in the lock.
+    fn flake() -> TestFlake {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.nodes.get_mut("crane").unwrap().inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+        flake_lock.nodes.get_mut("nixpkgs_2").unwrap().inputs = inputs("flake-utils");
+        let mut flake_utils = github_node_with_ref(
+            "numtide",
+            "flake-utils",
+            &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+            &OriginalRef::from("main"),
+        );
+        flake_utils.inputs.clear();
+        flake_lock
+            .nodes
+            .insert(String::from("flake-utils"), flake_utils);
+
+        TestFlake::default().with_lock("flake", flake_lock)
+    }
+
+    #[test]
+    fn test_gc_lock_removes_unreachable_nodes() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        gc_lock(&PathBuf::from("flake"), false, &flake, &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            r"removed 2 nodes from flake.lock:
+  flake-utils (github:numtide/flake-utils 3892194)
+  nixpkgs_2 (github:NixOS/nixpkgs dc1517e)"
+        );
+        let mut nodes = flake
+            .load_lock_from("flake")
+            .unwrap()
+            .nodes
+            .into_keys()
+            .collect::<Vec<_>>();
+        nodes.sort();
+        assert_eq!(nodes, vec!["crane", "nixpkgs"]);
+    }
+
+    #[test]
+    fn test_gc_lock_check() {
+        let console = TestConsole::default();
+        let flake = flake();
+
+        let result = gc_lock(&PathBuf::from("flake"), true, &flake, &console);
+
+        assert_eq!(result.unwrap_err().exit_code(), 5);
+        assert!(console
+            .output()
+            .starts_with("2 nodes unreachable from the root of flake.lock:"));
+        assert!(flake.removed_nodes.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_gc_lock_without_unreachable_nodes() {
+        let console = TestConsole::default();
+        let flake = flake();
+        gc_lock(&PathBuf::from("flake"), false, &flake, &console).unwrap();
+
+        let console = TestConsole::default();
+        gc_lock(&PathBuf::from("flake"), true, &flake, &console).unwrap();
+
+        assert_eq!(console.output(), "every node is reachable from the root");
+    }
+}
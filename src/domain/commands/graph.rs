@@ -0,0 +1,394 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock, InputReference, Node},
+    Result,
+};
+
+use super::format::short_rev;
+
+const ROOT: &str = "root";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+/// Prints the node graph of the flake.lock. Nodes are labeled by their source and revision,
+/// inputs are solid edges, inputs following another input are dashed edges. When `collapse` is
+/// set, nodes of the same source are merged into one node, when `highlight` is set, nodes sharing
+/// their source with other nodes are highlighted.
+pub fn graph<F: Flake, C: Console>(
+    path: Option<&Path>,
+    format: GraphFormat,
+    collapse: bool,
+    highlight: bool,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = match path {
+        Some(path) => flake.load_lock_from(path)?,
+        None => flake.load_lock()?,
+    };
+    let graph = Graph::new(&flake_lock, collapse);
+
+    match format {
+        GraphFormat::Dot => console.println(graph.dot(highlight)),
+        GraphFormat::Mermaid => console.println(graph.mermaid(highlight)),
+        GraphFormat::Json => console.println(
+            serde_json::to_string_pretty(&graph)
+                .map_err(|err| domain::Error::Error(err.to_string()))?,
+        ),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+struct GraphNode {
+    id: String,
+    source: Option<String>,
+    revs: Vec<String>,
+    /// Whether other nodes of the lock have the same source
+    shared: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct GraphEdge {
+    from: String,
+    to: String,
+    input: String,
+    follows: bool,
+}
+
+impl Graph {
+    fn new(flake_lock: &FlakeLock, collapse: bool) -> Self {
+        let mut nodes_by_source = BTreeMap::<String, Vec<(&String, &Node)>>::new();
+        for (name, node) in &flake_lock.nodes {
+            nodes_by_source
+                .entry(node.locked.source.normalized())
+                .or_default()
+                .push((name, node));
+        }
+
+        // the id of the graph node every lock node is drawn as
+        let mut ids = BTreeMap::new();
+        let mut nodes = vec![GraphNode {
+            id: String::from(ROOT),
+            source: None,
+            revs: vec![],
+            shared: false,
+        }];
+        for (normalized, mut group) in nodes_by_source {
+            group.sort_by_key(|(name, _)| *name);
+            let shared = group.len() > 1;
+
+            if collapse {
+                let revs = group
+                    .iter()
+                    .map(|(_, node)| String::from(&*node.locked.rev))
+                    .collect::<BTreeSet<_>>();
+                for (name, _) in &group {
+                    ids.insert(name.as_str(), normalized.clone());
+                }
+                nodes.push(GraphNode {
+                    id: normalized,
+                    source: Some(group[0].1.locked.source.to_string()),
+                    revs: revs.into_iter().collect(),
+                    shared,
+                });
+            } else {
+                for (name, node) in group {
+                    ids.insert(name.as_str(), name.clone());
+                    nodes.push(GraphNode {
+                        id: name.clone(),
+                        source: Some(node.locked.source.to_string()),
+                        revs: vec![String::from(&*node.locked.rev)],
+                        shared,
+                    });
+                }
+            }
+        }
+        nodes[1..].sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut edges = BTreeSet::new();
+        let inputs = flake_lock
+            .nodes
+            .iter()
+            .map(|(name, node)| (name.as_str(), &node.inputs))
+            .chain([(ROOT, &flake_lock.root.inputs)]);
+        for (name, inputs) in inputs {
+            let from = ids.get(name).map_or(ROOT, String::as_str);
+
+            for (input, reference) in inputs {
+                let (target, follows) = match reference {
+                    InputReference::Alias(target) => (Some(target.clone()), false),
+                    InputReference::Path(path) => (flake_lock.resolve_path(path), true),
+                };
+                let Some(to) = target.and_then(|target| ids.get(target.as_str())) else {
+                    log::warn!("{name}/{input} refers to a node that is not in the lock");
+                    continue;
+                };
+
+                edges.insert(GraphEdge {
+                    from: String::from(from),
+                    to: to.clone(),
+                    input: input.clone(),
+                    follows,
+                });
+            }
+        }
+
+        Self {
+            nodes,
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    fn label(node: &GraphNode, separator: &str) -> String {
+        match &node.source {
+            Some(source) => {
+                let revs = node
+                    .revs
+                    .iter()
+                    .map(|rev| short_rev(rev))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{source}{separator}{revs}")
+            }
+            None => node.id.clone(),
+        }
+    }
+
+    fn dot(&self, highlight: bool) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+
+        let mut lines = vec![String::from("digraph lock {")];
+        for node in &self.nodes {
+            let style = if highlight && node.shared {
+                ", style=filled, fillcolor=\"#ffcc80\""
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "  {} [label={}{style}];",
+                quote(&node.id),
+                quote(&Self::label(node, "\\n"))
+            ));
+        }
+        for edge in &self.edges {
+            let style = if edge.follows { ", style=dashed" } else { "" };
+            lines.push(format!(
+                "  {} -> {} [label={}{style}];",
+                quote(&edge.from),
+                quote(&edge.to),
+                quote(&edge.input)
+            ));
+        }
+        lines.push(String::from("}"));
+
+        lines.join("\n")
+    }
+
+    fn mermaid(&self, highlight: bool) -> String {
+        // node names aren't always valid mermaid ids, nodes are referred to by their index
+        let ids = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id.as_str(), format!("n{index}")))
+            .collect::<BTreeMap<_, _>>();
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+
+        let mut lines = vec![String::from("graph LR")];
+        for node in &self.nodes {
+            lines.push(format!(
+                "  {}[{}]",
+                ids[node.id.as_str()],
+                quote(&Self::label(node, "<br/>"))
+            ));
+        }
+        for edge in &self.edges {
+            let arrow = if edge.follows { "-.->" } else { "-->" };
+            lines.push(format!(
+                "  {} {arrow}|{}| {}",
+                ids[edge.from.as_str()],
+                quote(&edge.input),
+                ids[edge.to.as_str()]
+            ));
+        }
+
+        let shared = self
+            .nodes
+            .iter()
+            .filter(|node| node.shared)
+            .map(|node| ids[node.id.as_str()].as_str())
+            .collect::<Vec<_>>();
+        if highlight && !shared.is_empty() {
+            lines.push(String::from("  classDef shared fill:#ffcc80"));
+            lines.push(format!("  class {} shared", shared.join(",")));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, TestFlake},
+            FlakeLock, InputReference,
+        },
+    };
+
+    use super::{graph, GraphFormat};
+
+    /// A flake with two copies of nixpkgs: its own and the one `crane` brings, `rust-overlay`
+    /// follows the flake's nixpkgs.
+    fn flake_lock() -> FlakeLock {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.nodes.get_mut("crane").unwrap().inputs.insert(
+            String::from("rust-overlay"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+
+        flake_lock
+    }
+
+    fn print(format: GraphFormat, collapse: bool, highlight: bool) -> String {
+        let console = TestConsole::default();
+        let flake = TestFlake::default().with_lock(PathBuf::from("flake"), flake_lock());
+
+        graph(
+            Some(&PathBuf::from("flake")),
+            format,
+            collapse,
+            highlight,
+            &flake,
+            &console,
+        )
+        .unwrap();
+
+        console.output()
+    }
+
+    #[test]
+    fn test_graph_dot() {
+        assert_eq!(
+            print(GraphFormat::Dot, false, true),
+            r##"digraph lock {
+  "root" [label="root"];
+  "crane" [label="github:ipetkov/crane\n8b3ad2f"];
+  "nixpkgs" [label="github:NixOS/nixpkgs\na08e061", style=filled, fillcolor="#ffcc80"];
+  "nixpkgs_2" [label="github:NixOS/nixpkgs\ndc1517e", style=filled, fillcolor="#ffcc80"];
+  "crane" -> "nixpkgs" [label="rust-overlay", style=dashed];
+  "crane" -> "nixpkgs_2" [label="nixpkgs"];
+  "root" -> "crane" [label="crane"];
+  "root" -> "nixpkgs" [label="nixpkgs"];
+}"##
+        );
+    }
+
+    #[test]
+    fn test_graph_mermaid_collapsed() {
+        assert_eq!(
+            print(GraphFormat::Mermaid, true, true),
+            r#"graph LR
+  n0["root"]
+  n1["github:ipetkov/crane<br/>8b3ad2f"]
+  n2["github:NixOS/nixpkgs<br/>a08e061, dc1517e"]
+  n1 -->|"nixpkgs"| n2
+  n1 -.->|"rust-overlay"| n2
+  n0 -->|"crane"| n1
+  n0 -->|"nixpkgs"| n2
+  classDef shared fill:#ffcc80
+  class n2 shared"#
+        );
+    }
+
+    #[test]
+    fn test_graph_json() {
+        assert_eq!(
+            print(GraphFormat::Json, false, false),
+            r#"{
+  "nodes": [
+    {
+      "id": "root",
+      "source": null,
+      "revs": [],
+      "shared": false
+    },
+    {
+      "id": "crane",
+      "source": "github:ipetkov/crane",
+      "revs": [
+        "8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1"
+      ],
+      "shared": false
+    },
+    {
+      "id": "nixpkgs",
+      "source": "github:NixOS/nixpkgs",
+      "revs": [
+        "a08e061a4ee8329747d54ddf1566d34c55c895eb"
+      ],
+      "shared": true
+    },
+    {
+      "id": "nixpkgs_2",
+      "source": "github:NixOS/nixpkgs",
+      "revs": [
+        "dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad"
+      ],
+      "shared": true
+    }
+  ],
+  "edges": [
+    {
+      "from": "crane",
+      "to": "nixpkgs",
+      "input": "rust-overlay",
+      "follows": true
+    },
+    {
+      "from": "crane",
+      "to": "nixpkgs_2",
+      "input": "nixpkgs",
+      "follows": false
+    },
+    {
+      "from": "root",
+      "to": "crane",
+      "input": "crane",
+      "follows": false
+    },
+    {
+      "from": "root",
+      "to": "nixpkgs",
+      "input": "nixpkgs",
+      "follows": false
+    }
+  ]
+}"#
+        );
+    }
+}
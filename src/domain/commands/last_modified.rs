@@ -1,42 +1,1002 @@
-use time::format_description;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use comfy_table::{presets::UTF8_BORDERS_ONLY, Table};
+use serde::{Serialize, Serializer};
+use time::{
+    format_description::{self, well_known::Rfc3339, FormatItem},
+    Duration, OffsetDateTime,
+};
 
-use crate::domain::{console::Console, nix::Flake, Result};
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Color, Table};
 
-pub fn last_modified<F: Flake, C: Console>(flake: &F, console: &C) -> Result<()> {
-    let flake_lock = flake.load_lock()?;
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock, LockEdgeTarget, Node},
+    revision::RevisionProvider,
+    Result,
+};
+
+use super::{
+    format::{markdown_cell, relative_age, short_rev, tsv_field},
+    MaxAge, OutputFormat,
+};
+
+pub struct LastModifiedOptions {
+    pub format: OutputFormat,
+    /// The columns of the table, ignored by the machine readable formats.
+    pub columns: Vec<Column>,
+    pub sort: SortBy,
+    /// Walk the whole lock graph instead of printing the root inputs only. The inputs are printed
+    /// as a tree and `sort` is ignored.
+    pub transitive: bool,
+    /// Inputs older than this are highlighted and make the command fail with
+    /// [`domain::Error::StaleInputs`].
+    pub max_age: MaxAge,
+    pub now: OffsetDateTime,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Column {
+    Input,
+    Type,
+    Source,
+    Ref,
+    Rev,
+    Pinned,
+    Age,
+    LastModified,
+    /// Commit date of the locked revision, looked up using a [`RevisionProvider`]
+    CommitDate,
+    /// Author date of the locked revision, looked up using a [`RevisionProvider`]
+    AuthorDate,
+    /// Author of the locked revision, looked up using a [`RevisionProvider`]
+    Author,
+    /// First line of the commit message of the locked revision, looked up using a
+    /// [`RevisionProvider`]
+    Subject,
+    /// Refs the locked revision is reachable from, looked up using a [`RevisionProvider`]
+    Refs,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Input => "input",
+            Column::Type => "type",
+            Column::Source => "source",
+            Column::Ref => "ref",
+            Column::Rev => "rev",
+            Column::Pinned => "pinned",
+            Column::Age => "age",
+            Column::LastModified => "last_modified",
+            Column::CommitDate => "commit_date",
+            Column::AuthorDate => "author_date",
+            Column::Author => "author",
+            Column::Subject => "subject",
+            Column::Refs => "refs",
+        }
+    }
+
+    /// Whether the column needs the locked revision to be looked up.
+    #[must_use]
+    pub fn looks_up_revision(self) -> bool {
+        matches!(
+            self,
+            Column::CommitDate
+                | Column::AuthorDate
+                | Column::Author
+                | Column::Subject
+                | Column::Refs
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SortBy {
+    Name,
+    /// Oldest input first
+    Age,
+    Type,
+}
+
+/// Prints the last modified date/time of the inputs of the flake at `path`, or of the flake in
+/// the current directory.
+pub fn last_modified<F: Flake, R: RevisionProvider, C: Console>(
+    path: Option<&Path>,
+    options: &LastModifiedOptions,
+    flake: &F,
+    revisions: &R,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = match path {
+        Some(path) => flake.load_lock_from(path)?,
+        None => flake.load_lock()?,
+    };
+    let records = records(None, &flake_lock, options, revisions)?;
+
+    match options.format {
+        OutputFormat::Table => console.println(format!("{}", table(&records, options)?))?,
+        format => console.println(render(&records, &options.columns, format)?)?,
+    }
+
+    check_stale(&records)
+}
+
+/// Prints the last modified date/time of the inputs of each given flake. Tables are printed per
+/// flake, preceded by the flake's path, other formats get an additional `flake` field.
+pub fn last_modified_all<F: Flake, R: RevisionProvider, C: Console>(
+    flakes: &[PathBuf],
+    options: &LastModifiedOptions,
+    flake: &F,
+    revisions: &R,
+    console: &C,
+) -> Result<()> {
+    let mut all_records = Vec::new();
+
+    for path in flakes {
+        let flake_lock = flake.load_lock_from(path)?;
+        let records = records(
+            Some(&path.display().to_string()),
+            &flake_lock,
+            options,
+            revisions,
+        )?;
+
+        if options.format == OutputFormat::Table {
+            console.println(format!("{}\n{}", path.display(), table(&records, options)?))?;
+        }
+        all_records.extend(records);
+    }
+
+    match options.format {
+        OutputFormat::Table => {}
+        format => console.println(render(&all_records, &options.columns, format)?)?,
+    }
+
+    check_stale(&all_records)
+}
+
+fn check_stale(records: &[Record]) -> Result<()> {
+    let stale = records
+        .iter()
+        .filter_map(|record| {
+            record.stale.map(|max_age| {
+                let input = match &record.flake {
+                    Some(flake) => format!("{flake}#{}", record.input),
+                    None => record.input.clone(),
+                };
+                let max_age = if max_age.whole_hours() % 24 == 0 {
+                    format!("{} days", max_age.whole_days())
+                } else {
+                    format!("{} hours", max_age.whole_hours())
+                };
+                format!("{input} is older than {max_age} ({})", record.last_modified)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if stale.is_empty() {
+        Ok(())
+    } else {
+        Err(domain::Error::StaleInputs(stale))
+    }
+}
+
+/// A row of the output, the field names are part of the machine readable formats.
+#[derive(Debug, PartialEq, Serialize)]
+struct Record {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flake: Option<String>,
+    input: String,
+    r#type: String,
+    source: String,
+    r#ref: Option<String>,
+    rev: String,
+    last_modified: String,
+    last_modified_unix: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subject: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    refs: Vec<String>,
+    #[serde(skip)]
+    pinned: bool,
+    /// The depth of the input in the lock graph, 0 for root inputs.
+    #[serde(skip)]
+    depth: usize,
+    /// The path of the followed input when this input follows another one, only the table
+    /// contains these rows.
+    #[serde(skip)]
+    follows: Option<String>,
+    /// The path the input's node was first listed at when other inputs lock the same node, the
+    /// node's own inputs are only listed there.
+    #[serde(skip)]
+    see: Option<String>,
+    /// The exceeded maximum age when the input is older than allowed, machine readable formats
+    /// only flag the input.
+    #[serde(serialize_with = "serialize_stale")]
+    stale: Option<Duration>,
+}
+
+impl Record {
+    fn new(flake: Option<&str>, input: String, node: &Node) -> Result<Self> {
+        Ok(Record {
+            flake: flake.map(String::from),
+            r#type: String::from(node.locked.source.fetcher()),
+            source: node.locked.source.repository(),
+            r#ref: node
+                .original
+                .r#ref
+                .as_ref()
+                .map(|r| String::from(&**r))
+                .or_else(|| node.locked.r#ref.as_ref().map(|r| String::from(&**r))),
+            rev: String::from(&*node.locked.rev),
+            last_modified: format_rfc3339(node.locked.last_modified)?,
+            last_modified_unix: node.locked.last_modified.unix_timestamp(),
+            commit_date: None,
+            author_date: None,
+            author: None,
+            subject: None,
+            refs: vec![],
+            pinned: node.original.rev.is_some(),
+            depth: 0,
+            follows: None,
+            see: None,
+            stale: None,
+            input,
+        })
+    }
+
+    fn follows(flake: Option<&str>, input: String, follows: String) -> Self {
+        Record {
+            flake: flake.map(String::from),
+            input,
+            r#type: String::new(),
+            source: String::new(),
+            r#ref: None,
+            rev: String::new(),
+            last_modified: String::new(),
+            last_modified_unix: 0,
+            commit_date: None,
+            author_date: None,
+            author: None,
+            subject: None,
+            refs: vec![],
+            pinned: false,
+            depth: 0,
+            follows: Some(follows),
+            see: None,
+            stale: None,
+        }
+    }
+
+    /// Looks up the commit and author date of the locked revision, these stay empty when the
+    /// revision can't be found, and fall back to the last modified date when the lookup fails
+    /// (e.g. offline).
+    fn look_up_revision<R: RevisionProvider>(&mut self, node: &Node, revisions: &R) -> Result<()> {
+        match revisions.revision(&node.locked.source, &node.locked.rev) {
+            Ok(Some(revision)) => {
+                self.commit_date = Some(format_rfc3339(revision.commit_date)?);
+                self.author_date = Some(format_rfc3339(revision.author_date)?);
+                self.author = revision.author;
+                self.subject = revision.subject;
+                self.refs = revision.refs;
+            }
+            Ok(None) => log::warn!("revision {} of {} not found", self.rev, self.input),
+            Err(err) => {
+                log::warn!(
+                    "couldn't look up revision of {}, using its last modified date: {err}",
+                    self.input
+                );
+                self.commit_date = Some(self.last_modified.clone());
+                self.author_date = Some(self.last_modified.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The fields of the machine readable formats, followed by the requested columns looked up
+    /// from the revision, the refs are separated by spaces.
+    fn fields(&self, columns: &[Column]) -> Vec<String> {
+        let mut fields = self.flake.iter().cloned().collect::<Vec<_>>();
+        fields.extend([
+            self.input.clone(),
+            self.r#type.clone(),
+            self.source.clone(),
+            self.r#ref.clone().unwrap_or_default(),
+            self.rev.clone(),
+            self.last_modified.clone(),
+            self.last_modified_unix.to_string(),
+            self.stale.is_some().to_string(),
+        ]);
+        fields.extend(columns.iter().filter_map(|column| match column {
+            Column::CommitDate => Some(self.commit_date.clone().unwrap_or_default()),
+            Column::AuthorDate => Some(self.author_date.clone().unwrap_or_default()),
+            Column::Author => Some(self.author.clone().unwrap_or_default()),
+            Column::Subject => Some(self.subject.clone().unwrap_or_default()),
+            Column::Refs => Some(self.refs.join(" ")),
+            _ => None,
+        }));
+        fields
+    }
+}
+
+#[allow(clippy::ref_option)]
+fn serialize_stale<S: Serializer>(
+    stale: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_bool(stale.is_some())
+}
+
+const FIELD_NAMES: [&str; 8] = [
+    "input",
+    "type",
+    "source",
+    "ref",
+    "rev",
+    "last_modified",
+    "last_modified_unix",
+    "stale",
+];
+
+fn records<R: RevisionProvider>(
+    flake: Option<&str>,
+    flake_lock: &FlakeLock,
+    options: &LastModifiedOptions,
+    revisions: &R,
+) -> Result<Vec<Record>> {
+    let mut records = input_records(flake, flake_lock, options, revisions)?;
+
+    for record in records.iter_mut().filter(|r| r.follows.is_none()) {
+        let age = options.now.unix_timestamp() - record.last_modified_unix;
+        record.stale = options
+            .max_age
+            .of(&record.input)
+            .filter(|max_age| age > max_age.whole_seconds());
+    }
+
+    Ok(records)
+}
+
+fn format_rfc3339(date: OffsetDateTime) -> Result<String> {
+    date.format(&Rfc3339)
+        .map_err(|err| domain::Error::Error(err.to_string()))
+}
+
+fn input_records<R: RevisionProvider>(
+    flake: Option<&str>,
+    flake_lock: &FlakeLock,
+    options: &LastModifiedOptions,
+    revisions: &R,
+) -> Result<Vec<Record>> {
+    let new_record = |input: String, node: &Node| {
+        let mut record = Record::new(flake, input, node)?;
+        if options
+            .columns
+            .iter()
+            .any(|column| column.looks_up_revision())
+        {
+            record.look_up_revision(node, revisions)?;
+        }
+        Ok(record)
+    };
+
+    if options.transitive {
+        let mut listed_at = HashMap::<&str, String>::new();
+        let mut shared = Vec::<Vec<String>>::new();
+        let mut records = Vec::new();
+
+        for edge in flake_lock.edges() {
+            if shared
+                .iter()
+                .any(|path| edge.path.len() > path.len() && edge.path.starts_with(path))
+            {
+                continue;
+            }
+
+            let input = edge.path.join("/");
+            let mut record = match edge.target {
+                LockEdgeTarget::Node { node_name, node } => {
+                    let mut record = new_record(input.clone(), node)?;
+                    if let Some(first) = listed_at.get(node_name) {
+                        record.see = Some(first.clone());
+                        shared.push(edge.path.clone());
+                    } else {
+                        listed_at.insert(node_name, input);
+                    }
+                    record
+                }
+                LockEdgeTarget::Follows(path) => Record::follows(flake, input, path.join("/")),
+            };
+            record.depth = edge.path.len() - 1;
+            records.push(record);
+        }
+
+        return Ok(records);
+    }
 
     let nodes = flake_lock.input_nodes();
     let mut names: Vec<String> = nodes.keys().cloned().collect();
     names.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
+    match options.sort {
+        SortBy::Name => {}
+        SortBy::Age => names.sort_by_key(|name| nodes[name].locked.last_modified),
+        SortBy::Type => names.sort_by_key(|name| nodes[name].locked.source.fetcher()),
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let node = &nodes[&name];
+            new_record(name, node)
+        })
+        .collect()
+}
+
+fn table(records: &[Record], options: &LastModifiedOptions) -> Result<Table> {
     let mut table = Table::new();
     table.load_preset(UTF8_BORDERS_ONLY);
-    table.set_header(vec!["input", "last_modified"]);
+    table.set_header(
+        options
+            .columns
+            .iter()
+            .map(|c| c.header())
+            .collect::<Vec<_>>(),
+    );
 
-    let date_format =
-        format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
+    let date_format = format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]")
+        .map_err(|err| domain::Error::Error(err.to_string()))?;
 
-    for name in names {
-        let last_modified = nodes.get(&name).unwrap().locked.last_modified;
+    for record in records {
+        let last_modified = OffsetDateTime::from_unix_timestamp(record.last_modified_unix)
+            .map_err(|err| {
+                domain::Error::Error(format!("{}: invalid lastModified: {err}", record.input))
+            })?;
+        let last_modified_date = last_modified
+            .format(&date_format)
+            .map_err(|err| domain::Error::Error(err.to_string()))?;
+        let name = format!(
+            "{}{}",
+            "  ".repeat(record.depth),
+            record.input.rsplit('/').next().unwrap_or_default()
+        );
 
-        table.add_row(vec![
-            name.clone(),
-            last_modified.format(&date_format).unwrap(),
-        ]);
+        if let Some(follows) = &record.follows {
+            table.add_row(
+                options
+                    .columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Input => format!("{name} (follows {follows})"),
+                        _ => String::new(),
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            continue;
+        }
+
+        table.add_row(
+            options
+                .columns
+                .iter()
+                .map(|column| match column {
+                    Column::Input => match &record.see {
+                        Some(see) => format!("{name} (see {see})"),
+                        None => name.clone(),
+                    },
+                    Column::Type => record.r#type.clone(),
+                    Column::Source => record.source.clone(),
+                    Column::Ref => record.r#ref.clone().unwrap_or_default(),
+                    Column::Rev => String::from(short_rev(&record.rev)),
+                    Column::Pinned => String::from(if record.pinned { "yes" } else { "no" }),
+                    Column::Age => relative_age(options.now, last_modified),
+                    Column::LastModified => last_modified_date.clone(),
+                    Column::CommitDate => table_date(record.commit_date.as_deref(), &date_format),
+                    Column::AuthorDate => table_date(record.author_date.as_deref(), &date_format),
+                    Column::Author => record.author.clone().unwrap_or_else(|| String::from("-")),
+                    Column::Subject => record.subject.clone().unwrap_or_else(|| String::from("-")),
+                    Column::Refs => record.refs.join(", "),
+                })
+                .map(|content| {
+                    if record.stale.is_some() {
+                        Cell::new(content).fg(Color::Red)
+                    } else {
+                        Cell::new(content)
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
     }
 
-    console.println(format!("{table}"))?;
+    Ok(table)
+}
+
+fn table_date(rfc3339: Option<&str>, date_format: &[FormatItem]) -> String {
+    rfc3339
+        .and_then(|date| OffsetDateTime::parse(date, &Rfc3339).ok())
+        .and_then(|date| date.format(date_format).ok())
+        .unwrap_or_else(|| String::from("-"))
+}
+
+fn render(records: &[Record], columns: &[Column], format: OutputFormat) -> Result<String> {
+    let mut header = records
+        .first()
+        .and_then(|r| r.flake.as_ref())
+        .map(|_| vec!["flake"])
+        .unwrap_or_default();
+    header.extend(FIELD_NAMES);
+    header.extend(
+        columns
+            .iter()
+            .filter(|column| column.looks_up_revision())
+            .map(|column| column.header()),
+    );
+
+    let rows = records
+        .iter()
+        .filter(|record| record.follows.is_none())
+        .map(|record| record.fields(columns));
+
+    let lines = match format {
+        OutputFormat::Json => {
+            return serde_json::to_string_pretty(
+                &records
+                    .iter()
+                    .filter(|record| record.follows.is_none())
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|err| domain::Error::Error(err.to_string()))
+        }
+        OutputFormat::Table => unreachable!("tables are rendered by comfy_table"),
+        OutputFormat::Csv => std::iter::once(header.join(","))
+            .chain(rows.map(|fields| {
+                fields
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }))
+            .collect::<Vec<_>>(),
+        OutputFormat::Tsv => std::iter::once(header.join("\t"))
+            .chain(rows.map(|fields| {
+                fields
+                    .iter()
+                    .map(|field| tsv_field(field))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            }))
+            .collect::<Vec<_>>(),
+        OutputFormat::Markdown => [
+            format!("| {} |", header.join(" | ")),
+            format!("|{}", "---|".repeat(header.len())),
+        ]
+        .into_iter()
+        .chain(rows.map(|fields| {
+            let cells = fields
+                .iter()
+                .map(|field| markdown_cell(field))
+                .collect::<Vec<_>>();
+            format!("| {} |", cells.join(" | "))
+        }))
+        .collect::<Vec<_>>(),
+    };
 
-    Ok(())
+    Ok(lines.join("\n"))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use std::path::{Path, PathBuf};
+
+    use time::OffsetDateTime;
+
+    use crate::domain::{
+        commands::OutputFormat,
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{flake_lock_with_node, github_node_with_ref, TestFlake},
+            InputReference, LockedRev, OriginalRef,
+        },
+        revision::{fixtures::TestRevisionProvider, Revision},
+    };
+
+    use super::{last_modified, Column, LastModifiedOptions, MaxAge, SortBy};
+
+    fn options(format: OutputFormat, columns: Vec<Column>, sort: SortBy) -> LastModifiedOptions {
+        LastModifiedOptions {
+            format,
+            columns,
+            sort,
+            transitive: false,
+            max_age: MaxAge::default(),
+            #[allow(clippy::unreadable_literal)]
+            now: OffsetDateTime::from_unix_timestamp(1687559532).unwrap(),
+        }
+    }
+
+    fn flake() -> TestFlake {
+        TestFlake::default().with_lock(
+            ".",
+            flake_lock_with_node(
+                "nix-rust-utils",
+                github_node_with_ref(
+                    "voidcontext",
+                    "nix-rust-utils",
+                    &LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+                    &OriginalRef::from("refs/tags/v0.3.0"),
+                ),
+            ),
+        )
+    }
+
     #[test]
-    #[ignore = "Not implemented"]
     fn test_last_modified_prints_table() {
-        todo!()
+        let console = TestConsole::default();
+
+        last_modified(
+            None,
+            &options(
+                OutputFormat::Table,
+                vec![Column::Input, Column::LastModified],
+                SortBy::Name,
+            ),
+            &flake(),
+            &TestRevisionProvider::default(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌──────────────────────────────────────┐
+│ input            last_modified       │
+╞══════════════════════════════════════╡
+│ nix-rust-utils   2023-05-31 22:32:12 │
+└──────────────────────────────────────┘"
+        );
+    }
+
+    #[rstest]
+    #[case(OutputFormat::Csv, "input,type,source,ref,rev,last_modified,last_modified_unix,stale
+nix-rust-utils,github,voidcontext/nix-rust-utils,refs/tags/v0.3.0,3892194d7b3293de8b30f1d19e2af45ba41ba8fd,2023-05-31T22:32:12Z,1685572332,false")]
+    #[case(OutputFormat::Tsv, "input\ttype\tsource\tref\trev\tlast_modified\tlast_modified_unix\tstale
+nix-rust-utils\tgithub\tvoidcontext/nix-rust-utils\trefs/tags/v0.3.0\t3892194d7b3293de8b30f1d19e2af45ba41ba8fd\t2023-05-31T22:32:12Z\t1685572332\tfalse")]
+    #[case(OutputFormat::Markdown, "| input | type | source | ref | rev | last_modified | last_modified_unix | stale |
+|---|---|---|---|---|---|---|---|
+| nix-rust-utils | github | voidcontext/nix-rust-utils | refs/tags/v0.3.0 | 3892194d7b3293de8b30f1d19e2af45ba41ba8fd | 2023-05-31T22:32:12Z | 1685572332 | false |")]
+    #[case(
+        OutputFormat::Json,
+        r#"[
+  {
+    "input": "nix-rust-utils",
+    "type": "github",
+    "source": "voidcontext/nix-rust-utils",
+    "ref": "refs/tags/v0.3.0",
+    "rev": "3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+    "last_modified": "2023-05-31T22:32:12Z",
+    "last_modified_unix": 1685572332,
+    "stale": false
+  }
+]"#
+    )]
+    fn test_last_modified_prints_machine_readable_formats(
+        #[case] format: OutputFormat,
+        #[case] expected: &str,
+    ) {
+        let console = TestConsole::default();
+
+        last_modified(
+            None,
+            &options(format, vec![], SortBy::Name),
+            &flake(),
+            &TestRevisionProvider::default(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(console.output(), expected);
+    }
+
+    #[test]
+    fn test_last_modified_prints_selected_columns_sorted() {
+        let console = TestConsole::default();
+        let mut flake_lock = flake().flake_locks.remove(&PathBuf::from(".")).unwrap();
+        flake_lock.root.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+        let flake = TestFlake::default().with_lock(".", flake_lock);
+
+        last_modified(
+            None,
+            &options(
+                OutputFormat::Table,
+                vec![
+                    Column::Input,
+                    Column::Type,
+                    Column::Source,
+                    Column::Ref,
+                    Column::Rev,
+                    Column::Pinned,
+                    Column::Age,
+                ],
+                SortBy::Age,
+            ),
+            &flake,
+            &TestRevisionProvider::default(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌──────────────────────────────────────────────────────────────────────────────────────────────────────────┐
+│ input            type     source                       ref                rev       pinned   age         │
+╞══════════════════════════════════════════════════════════════════════════════════════════════════════════╡
+│ nixpkgs          github   NixOS/nixpkgs                                   a08e061   no       1 month ago │
+│ nix-rust-utils   github   voidcontext/nix-rust-utils   refs/tags/v0.3.0   3892194   no       23 days ago │
+└──────────────────────────────────────────────────────────────────────────────────────────────────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_last_modified_prints_transitive_inputs_as_tree() {
+        let console = TestConsole::default();
+        let mut flake_lock = flake().flake_locks.remove(&PathBuf::from(".")).unwrap();
+        let node = flake_lock.nodes.get_mut("nix-rust-utils").unwrap();
+        node.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+        node.inputs.insert(
+            String::from("nixpkgs-unstable"),
+            InputReference::Path(vec![
+                String::from("nix-rust-utils"),
+                String::from("nixpkgs"),
+            ]),
+        );
+        let flake = TestFlake::default().with_lock("flake", flake_lock);
+        let mut options = options(
+            OutputFormat::Table,
+            vec![Column::Input, Column::LastModified],
+            SortBy::Name,
+        );
+        options.transitive = true;
+
+        last_modified(
+            Some(Path::new("flake")),
+            &options,
+            &flake,
+            &TestRevisionProvider::default(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌───────────────────────────────────────────────────────────────────────────┐
+│ input                                                 last_modified       │
+╞═══════════════════════════════════════════════════════════════════════════╡
+│ nix-rust-utils                                        2023-05-31 22:32:12 │
+│   nixpkgs                                             2023-05-09 10:11:35 │
+│   nixpkgs-unstable (follows nix-rust-utils/nixpkgs)                       │
+└───────────────────────────────────────────────────────────────────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_last_modified_lists_the_inputs_of_shared_nodes_once() {
+        let console = TestConsole::default();
+        let mut flake_lock = flake().flake_locks.remove(&PathBuf::from(".")).unwrap();
+        flake_lock
+            .nodes
+            .get_mut("nix-rust-utils")
+            .unwrap()
+            .inputs
+            .insert(
+                String::from("nixpkgs"),
+                InputReference::Alias(String::from("nixpkgs")),
+            );
+        flake_lock.root.inputs.insert(
+            String::from("utils"),
+            InputReference::Alias(String::from("nix-rust-utils")),
+        );
+        let flake = TestFlake::default().with_lock("flake", flake_lock);
+        let mut options = options(
+            OutputFormat::Table,
+            vec![Column::Input, Column::LastModified],
+            SortBy::Name,
+        );
+        options.transitive = true;
+
+        last_modified(
+            Some(Path::new("flake")),
+            &options,
+            &flake,
+            &TestRevisionProvider::default(),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌──────────────────────────────────────────────────┐
+│ input                        last_modified       │
+╞══════════════════════════════════════════════════╡
+│ nix-rust-utils               2023-05-31 22:32:12 │
+│   nixpkgs                    2023-05-09 10:11:35 │
+│ utils (see nix-rust-utils)   2023-05-31 22:32:12 │
+└──────────────────────────────────────────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_last_modified_fails_with_stale_inputs() {
+        let console = TestConsole::default();
+        let mut options = options(OutputFormat::Csv, vec![], SortBy::Name);
+        options.max_age = MaxAge::parse(&["30d", "nix-rust-utils=7d"]).unwrap();
+
+        let result = last_modified(
+            None,
+            &options,
+            &flake(),
+            &TestRevisionProvider::default(),
+            &console,
+        );
+
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err(String::from(
+                "inputs are older than allowed: nix-rust-utils is older than 7 days (2023-05-31T22:32:12Z)"
+            ))
+        );
+        assert!(console
+            .output()
+            .contains("nix-rust-utils,github,voidcontext/nix-rust-utils,refs/tags/v0.3.0,3892194d7b3293de8b30f1d19e2af45ba41ba8fd,2023-05-31T22:32:12Z,1685572332,true"));
+    }
+
+    #[test]
+    fn test_last_modified_prints_commit_date_of_locked_revision() {
+        let console = TestConsole::default();
+        let mut flake_lock = flake().flake_locks.remove(&PathBuf::from(".")).unwrap();
+        flake_lock.root.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+        let flake = TestFlake::default().with_lock(".", flake_lock);
+        let mut revisions = TestRevisionProvider::default();
+        #[allow(clippy::unreadable_literal)]
+        revisions.revisions.insert(
+            String::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+            Revision {
+                commit_date: OffsetDateTime::from_unix_timestamp(1685570000).unwrap(),
+                author_date: OffsetDateTime::from_unix_timestamp(1685000000).unwrap(),
+                author: Some(String::from("voidcontext")),
+                subject: Some(String::from("Release v0.3.0")),
+                refs: vec![String::from("refs/tags/v0.3.0")],
+            },
+        );
+
+        last_modified(
+            None,
+            &options(
+                OutputFormat::Table,
+                vec![
+                    Column::Input,
+                    Column::CommitDate,
+                    Column::AuthorDate,
+                    Column::Author,
+                    Column::Subject,
+                    Column::Refs,
+                ],
+                SortBy::Name,
+            ),
+            &flake,
+            &revisions,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌──────────────────────────────────────────────────────────────────────────────────────────────────────────────┐
+│ input            commit_date           author_date           author        subject          refs             │
+╞══════════════════════════════════════════════════════════════════════════════════════════════════════════════╡
+│ nix-rust-utils   2023-05-31 21:53:20   2023-05-25 07:33:20   voidcontext   Release v0.3.0   refs/tags/v0.3.0 │
+│ nixpkgs          -                     -                     -             -                                 │
+└──────────────────────────────────────────────────────────────────────────────────────────────────────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_last_modified_adds_revision_columns_to_csv() {
+        let console = TestConsole::default();
+        let mut revisions = TestRevisionProvider::default();
+        #[allow(clippy::unreadable_literal)]
+        revisions.revisions.insert(
+            String::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd"),
+            Revision {
+                commit_date: OffsetDateTime::from_unix_timestamp(1685570000).unwrap(),
+                author_date: OffsetDateTime::from_unix_timestamp(1685000000).unwrap(),
+                author: Some(String::from("voidcontext")),
+                subject: Some(String::from("Release v0.3.0")),
+                refs: vec![
+                    String::from("refs/heads/main"),
+                    String::from("refs/tags/v0.3.0"),
+                ],
+            },
+        );
+
+        last_modified(
+            None,
+            &options(
+                OutputFormat::Csv,
+                vec![Column::Input, Column::Author, Column::Refs],
+                SortBy::Name,
+            ),
+            &flake(),
+            &revisions,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "input,type,source,ref,rev,last_modified,last_modified_unix,stale,author,refs
+nix-rust-utils,github,voidcontext/nix-rust-utils,refs/tags/v0.3.0,3892194d7b3293de8b30f1d19e2af45ba41ba8fd,2023-05-31T22:32:12Z,1685572332,false,voidcontext,refs/heads/main refs/tags/v0.3.0"
+        );
+    }
+
+    #[test]
+    fn test_last_modified_falls_back_to_last_modified_when_offline() {
+        let console = TestConsole::default();
+        let revisions = TestRevisionProvider {
+            offline: true,
+            ..TestRevisionProvider::default()
+        };
+
+        last_modified(
+            None,
+            &options(
+                OutputFormat::Table,
+                vec![Column::Input, Column::CommitDate],
+                SortBy::Name,
+            ),
+            &flake(),
+            &revisions,
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌──────────────────────────────────────┐
+│ input            commit_date         │
+╞══════════════════════════════════════╡
+│ nix-rust-utils   2023-05-31 22:32:12 │
+└──────────────────────────────────────┘"
+        );
     }
 }
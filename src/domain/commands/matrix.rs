@@ -0,0 +1,248 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Color, Table};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock},
+    Result,
+};
+
+use super::format::{relative_age, short_rev};
+
+/// Prints which revision of each input every flake is locked to, highlighting the revisions that
+/// diverge from the one most of the flakes are on.
+pub fn matrix<F: Flake, C: Console>(
+    flakes: &[PathBuf],
+    json: bool,
+    now: OffsetDateTime,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let flake_locks = flakes
+        .iter()
+        .map(|path| {
+            flake
+                .load_lock_from(path)
+                .map(|flake_lock| (path.display().to_string(), flake_lock))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let flake_names = flake_locks
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    let rows = rows(&flake_locks);
+
+    if json {
+        let matrix = Matrix {
+            flakes: flake_names,
+            inputs: rows,
+        };
+        let json = serde_json::to_string_pretty(&matrix)
+            .map_err(|err| domain::Error::Error(err.to_string()))?;
+
+        console.println(json)
+    } else {
+        console.println(format!("{}", table(&flake_names, &rows, now)))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Matrix {
+    flakes: Vec<String>,
+    inputs: Vec<Row>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Row {
+    input: String,
+    /// The cells of the row keyed by the flake's name, flakes without this input are missing.
+    flakes: BTreeMap<String, MatrixCell>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct MatrixCell {
+    rev: String,
+    r#ref: Option<String>,
+    #[serde(with = "time::serde::timestamp")]
+    last_modified: OffsetDateTime,
+    divergent: bool,
+}
+
+fn rows(flake_locks: &[(String, FlakeLock)]) -> Vec<Row> {
+    let input_nodes = flake_locks
+        .iter()
+        .map(|(name, flake_lock)| (name, flake_lock.input_nodes()))
+        .collect::<Vec<_>>();
+
+    let input_names = input_nodes
+        .iter()
+        .flat_map(|(_, nodes)| nodes.keys().cloned())
+        .collect::<BTreeSet<_>>();
+
+    input_names
+        .into_iter()
+        .map(|input| {
+            let mut flakes = input_nodes
+                .iter()
+                .filter_map(|(flake_name, nodes)| {
+                    nodes.get(&input).map(|node| {
+                        (
+                            (*flake_name).clone(),
+                            MatrixCell {
+                                rev: String::from(&*node.locked.rev),
+                                r#ref: node
+                                    .original
+                                    .r#ref
+                                    .as_ref()
+                                    .map(|r| String::from(&**r))
+                                    .or_else(|| {
+                                        node.locked.r#ref.as_ref().map(|r| String::from(&**r))
+                                    }),
+                                last_modified: node.locked.last_modified,
+                                divergent: false,
+                            },
+                        )
+                    })
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            mark_divergent(&mut flakes);
+
+            Row { input, flakes }
+        })
+        .collect()
+}
+
+/// Marks the cells that aren't on the most common revision of the row. When there's no single
+/// most common revision, every cell is marked.
+fn mark_divergent(cells: &mut BTreeMap<String, MatrixCell>) {
+    let mut rev_counts = HashMap::new();
+    for cell in cells.values() {
+        *rev_counts.entry(cell.rev.clone()).or_insert(0) += 1;
+    }
+
+    if rev_counts.len() < 2 {
+        return;
+    }
+
+    let max = rev_counts.values().max().copied().unwrap_or(0);
+    let mut most_common = rev_counts.iter().filter(|(_, count)| **count == max);
+    let majority_rev = match (most_common.next(), most_common.next()) {
+        (Some((rev, _)), None) => Some(rev.clone()),
+        _ => None,
+    };
+
+    for cell in cells.values_mut() {
+        cell.divergent = majority_rev.as_ref() != Some(&cell.rev);
+    }
+}
+
+fn table(flake_names: &[String], rows: &[Row], now: OffsetDateTime) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+
+    let mut header = vec![String::from("input")];
+    header.extend(flake_names.iter().cloned());
+    table.set_header(header);
+
+    for row in rows {
+        let mut cells = vec![Cell::new(&row.input)];
+
+        cells.extend(flake_names.iter().map(|flake_name| {
+            row.flakes.get(flake_name).map_or_else(
+                || Cell::new("-"),
+                |cell| {
+                    let mut content = String::from(short_rev(&cell.rev));
+                    if let Some(r#ref) = &cell.r#ref {
+                        content = format!("{content} ({ref})");
+                    }
+                    content = format!("{content}\n{}", relative_age(now, cell.last_modified));
+
+                    if cell.divergent {
+                        Cell::new(content).fg(Color::Red)
+                    } else {
+                        Cell::new(content)
+                    }
+                },
+            )
+        }));
+
+        table.add_row(cells);
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::nix::{
+        fixtures::{flake_lock_with_node, github_node_with_owner_and_repo_only},
+        LockedRev,
+    };
+
+    use super::rows;
+
+    #[test]
+    fn test_rows_marks_divergent_revisions() {
+        let hash_1 = LockedRev::from("f542386b0646cf39b9475a200979adabd07d98b2");
+        let hash_2 = LockedRev::from("4468e5deabf5e6d0740cd1a77df56f67093ec943");
+        let flake_lock = |rev: &LockedRev| {
+            flake_lock_with_node(
+                "nix-rust-utils",
+                github_node_with_owner_and_repo_only("voidcontext", "nix-rust-utils", rev),
+            )
+        };
+
+        let rows = rows(&[
+            (String::from("a"), flake_lock(&hash_1)),
+            (String::from("b"), flake_lock(&hash_1)),
+            (String::from("c"), flake_lock(&hash_2)),
+        ]);
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].input, "nix-rust-utils");
+        assert_eq!(
+            rows[0]
+                .flakes
+                .iter()
+                .map(|(flake, cell)| (flake.as_str(), cell.rev.as_str(), cell.divergent))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", &*hash_1, false),
+                ("b", &*hash_1, false),
+                ("c", &*hash_2, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rows_marks_every_cell_without_majority() {
+        let flake_lock = |rev: &str| {
+            flake_lock_with_node(
+                "nix-rust-utils",
+                github_node_with_owner_and_repo_only(
+                    "voidcontext",
+                    "nix-rust-utils",
+                    &LockedRev::from(rev),
+                ),
+            )
+        };
+
+        let rows = rows(&[
+            (String::from("a"), flake_lock("f542386b")),
+            (String::from("b"), flake_lock("4468e5de")),
+        ]);
+
+        assert!(rows[0].flakes.values().all(|cell| cell.divergent));
+    }
+}
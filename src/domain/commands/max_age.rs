@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use time::Duration;
+
+use crate::domain::{Error, Result};
+
+/// The maximum age of the inputs, with optional per input overrides.
+#[derive(Debug, Default, PartialEq)]
+pub struct MaxAge {
+    default: Option<Duration>,
+    per_input: HashMap<String, Duration>,
+}
+
+impl MaxAge {
+    /// Parses thresholds like `30d` and per input overrides like `nixpkgs=14d`, overrides of
+    /// transitive inputs name their full path, e.g. `crane/nixpkgs=14d`. The supported units are
+    /// `h` (hours), `d` (days), `w` (weeks) and `y` (365 days).
+    pub fn parse<S: AsRef<str>>(thresholds: &[S]) -> Result<Self> {
+        thresholds
+            .iter()
+            .try_fold(MaxAge::default(), |mut max_age, threshold| {
+                match threshold.as_ref().split_once('=') {
+                    Some((input, duration)) => {
+                        max_age
+                            .per_input
+                            .insert(input_path(input), parse_duration(duration)?);
+                    }
+                    None => max_age.default = Some(parse_duration(threshold.as_ref())?),
+                }
+                Ok(max_age)
+            })
+    }
+
+    /// The maximum age of the input at the given path, e.g. `crane/nixpkgs`, if there's any. An
+    /// override only applies to the input at its full path, `nixpkgs=14d` doesn't apply to
+    /// `crane/nixpkgs`.
+    #[must_use]
+    pub fn of(&self, input: &str) -> Option<Duration> {
+        self.per_input
+            .get(&input_path(input))
+            .copied()
+            .or(self.default)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.per_input.is_empty()
+    }
+}
+
+/// Normalises an input path, so `crane//nixpkgs/` names the same input as `crane/nixpkgs`.
+fn input_path(input: &str) -> String {
+    input
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || {
+        Error::Error(format!(
+            "Invalid age '{duration}', expected a number followed by h, d, w or y, e.g. 30d"
+        ))
+    };
+
+    let unit_index = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = duration.split_at(unit_index);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+
+    let unit_seconds = match unit {
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    amount
+        .checked_mul(unit_seconds)
+        .map(Duration::seconds)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use time::Duration;
+
+    use super::MaxAge;
+
+    #[test]
+    fn test_max_age_parses_default_and_overrides() {
+        let max_age = MaxAge::parse(&["30d", "nixpkgs=2w", "crane=12h"]).unwrap();
+
+        assert_eq!(max_age.of("nixpkgs"), Some(Duration::weeks(2)));
+        assert_eq!(max_age.of("crane"), Some(Duration::hours(12)));
+        assert_eq!(max_age.of("flake-utils"), Some(Duration::days(30)));
+    }
+
+    #[test]
+    fn test_max_age_overrides_match_the_full_input_path() {
+        let max_age = MaxAge::parse(&["30d", "nixpkgs=2w", "crane/nixpkgs=12h"]).unwrap();
+
+        assert_eq!(max_age.of("nixpkgs"), Some(Duration::weeks(2)));
+        assert_eq!(max_age.of("crane/nixpkgs"), Some(Duration::hours(12)));
+        assert_eq!(
+            max_age.of("crane/flake-utils/nixpkgs"),
+            Some(Duration::days(30))
+        );
+    }
+
+    #[test]
+    fn test_max_age_without_default() {
+        let max_age = MaxAge::parse(&["nixpkgs=1y"]).unwrap();
+
+        assert_eq!(max_age.of("nixpkgs"), Some(Duration::days(365)));
+        assert_eq!(max_age.of("flake-utils"), None);
+    }
+
+    #[test]
+    fn test_max_age_rejects_invalid_durations() {
+        for invalid in ["30", "d", "30x", "nixpkgs=", "-1d"] {
+            assert!(MaxAge::parse(&[invalid]).is_err(), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_max_age_rejects_durations_out_of_range() {
+        for invalid in [
+            "99999999999999y",
+            "nixpkgs=99999999999999w",
+            "9223372036854775808h",
+        ] {
+            assert!(MaxAge::parse(&[invalid]).is_err(), "{invalid} was accepted");
+        }
+    }
+}
@@ -1,10 +1,52 @@
+mod bump;
+mod bump_branch;
+mod dedupe;
+mod diff;
+mod dups;
+mod explain;
+mod format;
+mod gc_lock;
+mod graph;
 mod last_modified;
+mod matrix;
+mod max_age;
+mod outdated;
+mod status;
 mod sync;
+mod tags;
+mod unused_inputs;
+mod verify;
 
-pub use last_modified::last_modified;
-pub use sync::sync;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+pub use bump::{bump, BumpTarget};
+pub use bump_branch::{bump_branch, BranchPattern};
+pub use dedupe::dedupe;
+pub use diff::diff;
+pub use dups::dups;
+pub use explain::explain;
+pub use gc_lock::gc_lock;
+pub use graph::{graph, GraphFormat};
+pub use last_modified::{last_modified, last_modified_all, Column, LastModifiedOptions, SortBy};
+pub use matrix::matrix;
+pub use max_age::MaxAge;
+pub use outdated::outdated;
+pub use status::status;
+pub use sync::{check_sync, check_sync_all, sync, sync_all};
+pub use unused_inputs::unused_inputs;
+pub use verify::verify;
+
+/// The format of the output of the commands that support machine readable output.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Markdown,
+    Tsv,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum SyncInputNames {
     SourceAndDestination { source: String, destination: String },
     Same { input_name: String },
@@ -49,3 +91,15 @@ impl SyncInputNames {
         }
     }
 }
+
+impl fmt::Display for SyncInputNames {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SourceAndDestination {
+                source,
+                destination,
+            } => write!(f, "{source} -> {destination}"),
+            Self::Same { input_name } => write!(f, "{input_name}"),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::path::Path;
+
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Color, Table};
+use serde::Serialize;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{Flake, FlakeLock, Node},
+    remote::Remote,
+    Result,
+};
+
+use super::{format::short_rev, tags::newer_tags, tags::tag_version};
+
+/// Prints whether the tracked ref of each root input moved since it was locked, and the newer
+/// release tags of the inputs that are pinned to a tag.
+pub fn outdated<F: Flake, R: Remote, C: Console>(
+    path: Option<&Path>,
+    json: bool,
+    flake: &F,
+    remote: &R,
+    console: &C,
+) -> Result<()> {
+    let flake_lock = match path {
+        Some(path) => flake.load_lock_from(path)?,
+        None => flake.load_lock()?,
+    };
+    let rows = rows(&flake_lock, remote);
+
+    if json {
+        let json = serde_json::to_string_pretty(&rows)
+            .map_err(|err| domain::Error::Error(err.to_string()))?;
+
+        console.println(json)
+    } else {
+        console.println(format!("{}", table(&rows)))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    UpToDate,
+    /// The tracked ref points to a different commit than the locked one
+    Behind,
+    /// The input is pinned to a revision in flake.nix
+    Pinned,
+    /// The refs of the source couldn't be listed, or the tracked ref doesn't exist anymore
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Row {
+    input: String,
+    /// The tracked ref, the default branch when missing
+    r#ref: Option<String>,
+    locked: String,
+    latest: Option<String>,
+    status: Status,
+    newer_tags: Vec<String>,
+}
+
+fn rows<R: Remote>(flake_lock: &FlakeLock, remote: &R) -> Vec<Row> {
+    let nodes = flake_lock.input_nodes();
+    let mut names = nodes.keys().collect::<Vec<_>>();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| row(name, &nodes[name], remote))
+        .collect()
+}
+
+fn row<R: Remote>(input: &str, node: &Node, remote: &R) -> Row {
+    let mut row = Row {
+        input: String::from(input),
+        r#ref: node.original.r#ref.as_ref().map(|r| String::from(&**r)),
+        locked: String::from(&*node.locked.rev),
+        latest: None,
+        status: Status::Unknown,
+        newer_tags: vec![],
+    };
+
+    if node.original.rev.is_some() {
+        row.status = Status::Pinned;
+        return row;
+    }
+
+    let refs = match remote.refs(&node.locked.source.git_url()) {
+        Ok(refs) => refs,
+        Err(err) => {
+            log::warn!("couldn't list the refs of {input}: {err}");
+            return row;
+        }
+    };
+
+    row.latest = match &row.r#ref {
+        Some(r#ref) => refs.resolve(r#ref),
+        None => refs.head(),
+    }
+    .map(String::from);
+
+    row.status = match &row.latest {
+        Some(latest) if *latest == row.locked => Status::UpToDate,
+        Some(_) => Status::Behind,
+        None => Status::Unknown,
+    };
+
+    if let Some(r#ref) = row.r#ref.as_ref().filter(|r| tag_version(r).is_some()) {
+        row.newer_tags = newer_tags(r#ref, refs.tags())
+            .into_iter()
+            .map(String::from)
+            .collect();
+    }
+
+    row
+}
+
+fn table(rows: &[Row]) -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(vec![
+        "input",
+        "ref",
+        "locked",
+        "latest",
+        "status",
+        "newer tags",
+    ]);
+
+    for row in rows {
+        let status = match row.status {
+            Status::UpToDate => Cell::new("up to date"),
+            Status::Behind => Cell::new("behind").fg(Color::Red),
+            Status::Pinned => Cell::new("pinned"),
+            Status::Unknown => Cell::new("unknown").fg(Color::Yellow),
+        };
+
+        table.add_row(vec![
+            Cell::new(&row.input),
+            Cell::new(row.r#ref.as_deref().unwrap_or("-")),
+            Cell::new(short_rev(&row.locked)),
+            Cell::new(row.latest.as_deref().map_or("-", short_rev)),
+            status,
+            Cell::new(row.newer_tags.join(", ")),
+        ]);
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{flake_lock_with_node, github_node_with_ref, TestFlake},
+            InputReference, LockedRev, OriginalRef,
+        },
+        remote::{fixtures::TestRemote, RemoteRefs},
+    };
+
+    use super::outdated;
+
+    const LOCKED_REV: &str = "3892194d7b3293de8b30f1d19e2af45ba41ba8fd";
+    const NEWER_REV: &str = "8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1";
+
+    fn flake() -> TestFlake {
+        let mut flake_lock = flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_ref(
+                "voidcontext",
+                "nix-rust-utils",
+                &LockedRev::from(LOCKED_REV),
+                &OriginalRef::from("refs/tags/v0.3.0"),
+            ),
+        );
+        flake_lock.root.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+
+        TestFlake::default().with_lock(".", flake_lock)
+    }
+
+    fn remote() -> TestRemote {
+        let refs = |head: &str, refs: &[(&str, &str)]| {
+            RemoteRefs::new(
+                Some(String::from(head)),
+                refs.iter()
+                    .map(|(name, rev)| (String::from(*name), String::from(*rev)))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        };
+
+        TestRemote {
+            repositories: HashMap::from([
+                (
+                    String::from("https://github.com/voidcontext/nix-rust-utils.git"),
+                    refs(
+                        NEWER_REV,
+                        &[
+                            ("refs/heads/main", NEWER_REV),
+                            (
+                                "refs/tags/v0.2.0",
+                                "f542386b0646cf39b9475a200979adabd07d98b2",
+                            ),
+                            ("refs/tags/v0.3.0", LOCKED_REV),
+                            ("refs/tags/v0.4.0", NEWER_REV),
+                        ],
+                    ),
+                ),
+                (
+                    String::from("https://github.com/NixOS/nixpkgs.git"),
+                    refs(NEWER_REV, &[("refs/heads/master", NEWER_REV)]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_outdated_prints_table() {
+        let console = TestConsole::default();
+
+        outdated(None, false, &flake(), &remote(), &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            "┌─────────────────────────────────────────────────────────────────────────────────┐
+│ input            ref                locked    latest    status       newer tags │
+╞═════════════════════════════════════════════════════════════════════════════════╡
+│ nix-rust-utils   refs/tags/v0.3.0   3892194   3892194   up to date   v0.4.0     │
+│ nixpkgs          -                  a08e061   8b3ad2f   behind                  │
+└─────────────────────────────────────────────────────────────────────────────────┘"
+        );
+    }
+
+    #[test]
+    fn test_outdated_prints_json_with_unknown_status_when_offline() {
+        let console = TestConsole::default();
+
+        outdated(None, true, &flake(), &TestRemote::default(), &console).unwrap();
+
+        assert_eq!(
+            console.output(),
+            r#"[
+  {
+    "input": "nix-rust-utils",
+    "ref": "refs/tags/v0.3.0",
+    "locked": "3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+    "latest": null,
+    "status": "unknown",
+    "newer_tags": []
+  },
+  {
+    "input": "nixpkgs",
+    "ref": null,
+    "locked": "a08e061a4ee8329747d54ddf1566d34c55c895eb",
+    "latest": null,
+    "status": "unknown",
+    "newer_tags": []
+  }
+]"#
+        );
+    }
+}
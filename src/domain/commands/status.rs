@@ -0,0 +1,303 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use comfy_table::{presets::UTF8_BORDERS_ONLY, Table};
+
+use crate::domain::{
+    console::Console,
+    nix::{Flake, FlakeLock, InputReference, Node, SyncService, SyncStrategy},
+    Result,
+};
+
+use super::SyncInputNames;
+
+/// Compares the inputs of two flakes without modifying either of them.
+pub fn status<F: Flake, S: SyncService, C: Console>(
+    source: &Path,
+    destination: &Path,
+    by_source: bool,
+    flake: &F,
+    sync_service: &S,
+    console: &C,
+) -> Result<()> {
+    let source_flake_lock = flake.load_lock_from(source)?;
+    let destination_flake_lock = flake.load_lock_from(destination)?;
+
+    let status = Status::new(
+        &source_flake_lock,
+        &destination_flake_lock,
+        by_source,
+        sync_service,
+    );
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(vec!["input", "rev", "ref", "url", "follows", "strategy"]);
+
+    for input in &status.inputs {
+        table.add_row(vec![
+            input.input_names.to_string(),
+            matches(input.rev_matches),
+            matches(input.ref_matches),
+            matches(input.url_matches),
+            matches(input.follows_match),
+            input.strategy.to_string(),
+        ]);
+    }
+
+    console.println(format!("{table}"))?;
+
+    for input in &status.inputs {
+        if let Strategy::Unsupported(reason) = &input.strategy {
+            console.println(format!("{} can't be synced: {reason}", input.input_names))?;
+        }
+    }
+
+    if !status.only_in_source.is_empty() {
+        console.println(format!(
+            "only in source: {}",
+            status.only_in_source.join(", ")
+        ))?;
+    }
+    if !status.only_in_destination.is_empty() {
+        console.println(format!(
+            "only in destination: {}",
+            status.only_in_destination.join(", ")
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn matches(b: bool) -> String {
+    String::from(if b { "same" } else { "differs" })
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Status {
+    pub(crate) inputs: Vec<InputStatus>,
+    pub(crate) only_in_source: Vec<String>,
+    pub(crate) only_in_destination: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+#[allow(clippy::struct_excessive_bools)]
+pub(crate) struct InputStatus {
+    pub(crate) input_names: SyncInputNames,
+    pub(crate) rev_matches: bool,
+    pub(crate) ref_matches: bool,
+    pub(crate) url_matches: bool,
+    pub(crate) follows_match: bool,
+    pub(crate) strategy: Strategy,
+}
+
+/// The strategy that [`SyncService::sync_strategy`] would choose for an input.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Strategy {
+    LockOnly,
+    FlakeNixAndLock,
+    Unsupported(String),
+}
+
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::LockOnly => write!(f, "lock only"),
+            Strategy::FlakeNixAndLock => write!(f, "flake.nix + lock"),
+            Strategy::Unsupported(_) => write!(f, "unsupported"),
+        }
+    }
+}
+
+impl Status {
+    /// Pairs the root inputs of the two flakes by name, and when `by_source` is set the remaining
+    /// inputs by their locked source too.
+    pub(crate) fn new<S: SyncService>(
+        source: &FlakeLock,
+        destination: &FlakeLock,
+        by_source: bool,
+        sync_service: &S,
+    ) -> Self {
+        let source_nodes = source.input_nodes().into_iter().collect::<BTreeMap<_, _>>();
+        let destination_nodes = destination
+            .input_nodes()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+
+        let mut only_in_source = source_nodes.keys().cloned().collect::<BTreeSet<_>>();
+        let mut only_in_destination = destination_nodes.keys().cloned().collect::<BTreeSet<_>>();
+
+        let mut pairs = source_nodes
+            .keys()
+            .filter(|name| destination_nodes.contains_key(*name))
+            .map(|name| SyncInputNames::same(name.clone()))
+            .collect::<Vec<_>>();
+
+        for pair in &pairs {
+            only_in_source.remove(pair.source());
+            only_in_destination.remove(pair.destination());
+        }
+
+        if by_source {
+            for source_name in only_in_source.clone() {
+                let source_node = &source_nodes[&source_name];
+                let destination_name = only_in_destination
+                    .iter()
+                    .find(|name| {
+                        destination_nodes[*name].locked.source == source_node.locked.source
+                    })
+                    .cloned();
+
+                if let Some(destination_name) = destination_name {
+                    only_in_source.remove(&source_name);
+                    only_in_destination.remove(&destination_name);
+                    pairs.push(SyncInputNames::source_and_destination(
+                        source_name,
+                        destination_name,
+                    ));
+                }
+            }
+        }
+
+        let inputs = pairs
+            .into_iter()
+            .map(|input_names| {
+                let source_node = &source_nodes[input_names.source()];
+                let destination_node = &destination_nodes[input_names.destination()];
+
+                let strategy = match sync_service.sync_strategy(source, destination, &input_names) {
+                    Ok(SyncStrategy::LockOnly { .. }) => Strategy::LockOnly,
+                    Ok(SyncStrategy::FlakeNixAndLock { .. }) => Strategy::FlakeNixAndLock,
+                    Err(err) => Strategy::Unsupported(err.to_string()),
+                };
+
+                InputStatus {
+                    rev_matches: source_node.locked.rev == destination_node.locked.rev,
+                    ref_matches: source_node.original.r#ref == destination_node.original.r#ref
+                        && source_node.original.rev == destination_node.original.rev,
+                    url_matches: source_node.original.source == destination_node.original.source,
+                    follows_match: follows(source_node) == follows(destination_node),
+                    strategy,
+                    input_names,
+                }
+            })
+            .collect();
+
+        Self {
+            inputs,
+            only_in_source: only_in_source.into_iter().collect(),
+            only_in_destination: only_in_destination.into_iter().collect(),
+        }
+    }
+}
+
+fn follows(node: &Node) -> BTreeMap<&String, &Vec<String>> {
+    node.inputs
+        .iter()
+        .filter_map(|(name, reference)| match reference {
+            InputReference::Alias(_) => None,
+            InputReference::Path(path) => Some((name, path)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        commands::SyncInputNames,
+        nix::{
+            fixtures::{
+                flake_lock_with_node, github_node_with_owner_and_repo_only, github_node_with_ref,
+            },
+            InputReference, LockedRev, OriginalRef, SyncServiceImpl,
+        },
+    };
+
+    use super::{InputStatus, Status, Strategy};
+
+    #[test]
+    fn test_status_compares_inputs_paired_by_name() {
+        let source = flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_ref(
+                "voidcontext",
+                "nix-rust-utils",
+                &LockedRev::from("f542386b0646cf39b9475a200979adabd07d98b2"),
+                &OriginalRef::from("main"),
+            ),
+        );
+        let mut destination_node = github_node_with_owner_and_repo_only(
+            "voidcontext",
+            "nix-rust-utils",
+            &LockedRev::from("4468e5deabf5e6d0740cd1a77df56f67093ec943"),
+        );
+        destination_node.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+        let destination = flake_lock_with_node("nix-rust-utils", destination_node);
+
+        let status = Status::new(&source, &destination, false, &SyncServiceImpl {});
+
+        assert_eq!(
+            status,
+            Status {
+                inputs: vec![InputStatus {
+                    input_names: SyncInputNames::same(String::from("nix-rust-utils")),
+                    rev_matches: false,
+                    ref_matches: false,
+                    url_matches: true,
+                    follows_match: false,
+                    strategy: Strategy::FlakeNixAndLock,
+                }],
+                only_in_source: vec![],
+                only_in_destination: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_status_pairs_remaining_inputs_by_source() {
+        let rev = LockedRev::from("f542386b0646cf39b9475a200979adabd07d98b2");
+        let source = flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_owner_and_repo_only("voidcontext", "nix-rust-utils", &rev),
+        );
+        let mut destination = flake_lock_with_node(
+            "utils",
+            github_node_with_owner_and_repo_only("voidcontext", "nix-rust-utils", &rev),
+        );
+        destination.root.inputs = [(
+            String::from("utils"),
+            InputReference::Alias(String::from("utils")),
+        )]
+        .into_iter()
+        .collect();
+
+        let by_name = Status::new(&source, &destination, false, &SyncServiceImpl {});
+        assert_eq!(by_name.only_in_source, vec!["nix-rust-utils"]);
+        assert_eq!(by_name.only_in_destination, vec!["utils"]);
+
+        let by_source = Status::new(&source, &destination, true, &SyncServiceImpl {});
+        assert_eq!(
+            by_source
+                .inputs
+                .iter()
+                .map(|input| (&input.input_names, input.rev_matches))
+                .collect::<Vec<_>>(),
+            vec![(
+                &SyncInputNames::source_and_destination(
+                    String::from("nix-rust-utils"),
+                    String::from("utils")
+                ),
+                true
+            )]
+        );
+        assert!(by_source.only_in_source.is_empty());
+        assert!(by_source.only_in_destination.is_empty());
+    }
+}
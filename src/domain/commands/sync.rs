@@ -1,12 +1,13 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::domain::{
     self,
-    nix::{Flake, SyncService, SyncStrategy},
+    console::Console,
+    nix::{Flake, FlakeLock, LockedRev, SyncService, SyncStrategy},
     Result,
 };
 
-use super::SyncInputNames;
+use super::{format::short_rev, SyncInputNames};
 
 pub fn sync<F: Flake, S: SyncService>(
     source: &Path,
@@ -18,7 +19,154 @@ pub fn sync<F: Flake, S: SyncService>(
     let source_flake_lock = flake.load_lock_from(source)?;
     let destination_flake_lock = flake.load_lock_from(destination)?;
 
-    let strategies = inputs
+    let strategies = plan(
+        &source_flake_lock,
+        &destination_flake_lock,
+        inputs,
+        sync_service,
+    )?
+    .into_iter()
+    .map(|planned| planned.strategy)
+    .collect::<Vec<_>>();
+
+    let source_flake_nix = flake.load_from(source)?;
+    let destination_flake_nix = flake.load_from(destination)?;
+
+    let modified_flake_nix_content =
+        strategies
+            .iter()
+            .try_fold(destination_flake_nix, |result, strategy| match strategy {
+                SyncStrategy::LockOnly {
+                    lock_url: _,
+                    input_names: _,
+                } => Ok(result),
+                SyncStrategy::FlakeNixAndLock {
+                    lock_url: _,
+                    input_names,
+                } => sync_service.sync(&source_flake_nix, &result, input_names),
+            })?;
+
+    flake.write(destination, &modified_flake_nix_content)?;
+
+    strategies
+        .iter()
+        .map(|strategy| match strategy {
+            SyncStrategy::LockOnly {
+                lock_url,
+                input_names,
+            }
+            | SyncStrategy::FlakeNixAndLock {
+                lock_url,
+                input_names,
+            } => flake.override_input(destination, input_names.destination(), lock_url),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(())
+}
+
+/// Syncs the inputs into every destination flake that has at least one of them, the source flake
+/// itself is skipped.
+pub fn sync_all<F: Flake, S: SyncService>(
+    source: &Path,
+    destinations: &[PathBuf],
+    inputs: &[SyncInputNames],
+    flake: &F,
+    sync_service: &S,
+) -> Result<()> {
+    for destination in destinations.iter().filter(|d| d.as_path() != source) {
+        let destination_inputs = destination_inputs(destination, inputs, flake)?;
+
+        if destination_inputs.is_empty() {
+            log::info!("skipping {destination:?}: none of the inputs are present");
+            continue;
+        }
+
+        log::info!("syncing {destination:?}");
+        sync(
+            source,
+            destination,
+            &destination_inputs,
+            flake,
+            sync_service,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Computes the same strategies as [`sync`] but instead of modifying the destination it reports
+/// the inputs that would change, and fails with [`domain::Error::OutOfSync`] if there's any.
+pub fn check_sync<F: Flake, S: SyncService, C: Console>(
+    source: &Path,
+    destination: &Path,
+    inputs: &[SyncInputNames],
+    flake: &F,
+    sync_service: &S,
+    console: &C,
+) -> Result<()> {
+    let drifted = drifted_inputs(source, destination, inputs, flake, sync_service, console)?;
+
+    if drifted.is_empty() {
+        Ok(())
+    } else {
+        Err(domain::Error::OutOfSync(drifted))
+    }
+}
+
+/// Same as [`check_sync`], but checks every destination flake that has at least one of the inputs.
+pub fn check_sync_all<F: Flake, S: SyncService, C: Console>(
+    source: &Path,
+    destinations: &[PathBuf],
+    inputs: &[SyncInputNames],
+    flake: &F,
+    sync_service: &S,
+    console: &C,
+) -> Result<()> {
+    let mut drifted = Vec::new();
+
+    for destination in destinations.iter().filter(|d| d.as_path() != source) {
+        let destination_inputs = destination_inputs(destination, inputs, flake)?;
+
+        if !destination_inputs.is_empty() {
+            drifted.extend(drifted_inputs(
+                source,
+                destination,
+                &destination_inputs,
+                flake,
+                sync_service,
+                console,
+            )?);
+        }
+    }
+
+    if drifted.is_empty() {
+        Ok(())
+    } else {
+        Err(domain::Error::OutOfSync(drifted))
+    }
+}
+
+struct PlannedSync<'a> {
+    strategy: SyncStrategy<'a>,
+    source_rev: LockedRev,
+    destination_rev: LockedRev,
+}
+
+impl PlannedSync<'_> {
+    fn changes_destination(&self) -> bool {
+        self.source_rev != self.destination_rev
+            || matches!(self.strategy, SyncStrategy::FlakeNixAndLock { .. })
+    }
+}
+
+fn plan<'a, S: SyncService>(
+    source_flake_lock: &FlakeLock,
+    destination_flake_lock: &FlakeLock,
+    inputs: &'a [SyncInputNames],
+    sync_service: &'a S,
+) -> Result<Vec<PlannedSync<'a>>> {
+    inputs
         .iter()
         .map(|input_name| {
             let source_rev = source_flake_lock
@@ -50,42 +198,158 @@ pub fn sync<F: Flake, S: SyncService>(
             );
             log::debug!("source rev of {} is: {}", input_name.source(), &*source_rev);
 
-            sync_service.sync_strategy(&source_flake_lock, &destination_flake_lock, input_name)
+            let strategy = sync_service.sync_strategy(
+                source_flake_lock,
+                destination_flake_lock,
+                input_name,
+            )?;
+
+            Ok(PlannedSync {
+                strategy,
+                source_rev,
+                destination_rev,
+            })
         })
-        .collect::<Result<Vec<SyncStrategy>>>()?;
+        .collect()
+}
 
-    let source_flake_nix = flake.load_from(source)?;
-    let destination_flake_nix = flake.load_from(destination)?;
+fn drifted_inputs<F: Flake, S: SyncService, C: Console>(
+    source: &Path,
+    destination: &Path,
+    inputs: &[SyncInputNames],
+    flake: &F,
+    sync_service: &S,
+    console: &C,
+) -> Result<Vec<String>> {
+    let source_flake_lock = flake.load_lock_from(source)?;
+    let destination_flake_lock = flake.load_lock_from(destination)?;
 
-    let modified_flake_nix_content =
-        strategies
-            .iter()
-            .try_fold(destination_flake_nix, |result, strategy| match strategy {
-                SyncStrategy::LockOnly {
-                    lock_url: _,
-                    input_names: _,
-                } => Ok(result),
-                SyncStrategy::FlakeNixAndLock {
-                    lock_url: _,
-                    input_names,
-                } => sync_service.sync(&source_flake_nix, &result, input_names),
-            })?;
+    plan(
+        &source_flake_lock,
+        &destination_flake_lock,
+        inputs,
+        sync_service,
+    )?
+    .iter()
+    .filter(|planned| planned.changes_destination())
+    .map(|planned| {
+        let (input_names, what) = match &planned.strategy {
+            SyncStrategy::LockOnly { input_names, .. } => (input_names, "lock only"),
+            SyncStrategy::FlakeNixAndLock { input_names, .. } => (input_names, "flake.nix + lock"),
+        };
 
-    flake.write(destination, &modified_flake_nix_content)?;
+        console.println(format!(
+            "{}: {input_names}: {} -> {} ({what})",
+            destination.display(),
+            short_rev(&planned.destination_rev),
+            short_rev(&planned.source_rev),
+        ))?;
 
-    strategies
+        Ok(format!(
+            "{}#{}",
+            destination.display(),
+            input_names.destination()
+        ))
+    })
+    .collect()
+}
+
+fn destination_inputs<F: Flake>(
+    destination: &Path,
+    inputs: &[SyncInputNames],
+    flake: &F,
+) -> Result<Vec<SyncInputNames>> {
+    let destination_flake_lock = flake.load_lock_from(destination)?;
+
+    Ok(inputs
         .iter()
-        .map(|strategy| match strategy {
-            SyncStrategy::LockOnly {
-                lock_url,
-                input_names,
-            }
-            | SyncStrategy::FlakeNixAndLock {
-                lock_url,
-                input_names,
-            } => flake.override_input(destination, input_names.destination(), lock_url),
+        .filter(|input_name| {
+            destination_flake_lock
+                .root
+                .inputs
+                .contains_key(input_name.destination())
         })
-        .collect::<Result<Vec<_>>>()?;
+        .cloned()
+        .collect())
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        commands::SyncInputNames,
+        nix::{
+            fixtures::{
+                flake_lock_with_node, github_node_with_owner_and_repo_only, github_node_with_ref,
+                TestFlake,
+            },
+            Flake, LockedRev, OriginalRef, SyncServiceImpl,
+        },
+    };
+
+    use super::sync_all;
+
+    const SOURCE_FLAKE_NIX: &str = r#"{
+  inputs.nix-rust-utils.url = "github:voidcontext/nix-rust-utils/main";
+  outputs = { self, nix-rust-utils }: {};
+}"#;
+
+    const DESTINATION_FLAKE_NIX: &str = r#"{
+  inputs.nix-rust-utils.url = "github:voidcontext/nix-rust-utils";
+  outputs = { self, nix-rust-utils }: {};
+}"#;
+
+    const UNRELATED_FLAKE_NIX: &str = r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  outputs = { self, nixpkgs }: {};
+}"#;
+
+    #[test]
+    fn test_sync_all_syncs_destinations_having_the_inputs() {
+        let source_lock = flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_ref(
+                "voidcontext",
+                "nix-rust-utils",
+                &LockedRev::from("f542386b0646cf39b9475a200979adabd07d98b2"),
+                &OriginalRef::from("main"),
+            ),
+        );
+        let destination_lock = flake_lock_with_node(
+            "nix-rust-utils",
+            github_node_with_owner_and_repo_only(
+                "voidcontext",
+                "nix-rust-utils",
+                &LockedRev::from("4468e5deabf5e6d0740cd1a77df56f67093ec943"),
+            ),
+        );
+        let mut unrelated_lock = destination_lock.clone();
+        unrelated_lock.root.inputs.clear();
+
+        let flake = TestFlake::default()
+            .with_lock("tree/source", source_lock)
+            .with_flake_nix("tree/source", SOURCE_FLAKE_NIX)
+            .with_lock("tree/destination", destination_lock)
+            .with_flake_nix("tree/destination", DESTINATION_FLAKE_NIX)
+            .with_lock("tree/unrelated", unrelated_lock)
+            .with_flake_nix("tree/unrelated", UNRELATED_FLAKE_NIX);
+        let destinations = flake.discover("tree").unwrap();
+
+        sync_all(
+            &PathBuf::from("tree/source"),
+            &destinations,
+            &[SyncInputNames::same(String::from("nix-rust-utils"))],
+            &flake,
+            &SyncServiceImpl {},
+        )
+        .unwrap();
+
+        let flake_nix = |path: &str| flake.load_from(path).unwrap().as_string();
+        assert_eq!(flake_nix("tree/source"), SOURCE_FLAKE_NIX);
+        assert_eq!(flake_nix("tree/destination"), SOURCE_FLAKE_NIX);
+        assert_eq!(flake_nix("tree/unrelated"), UNRELATED_FLAKE_NIX);
+    }
 }
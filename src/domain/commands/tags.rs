@@ -0,0 +1,56 @@
+use semver::Version;
+
+/// Parses a tag like `v0.3.0`, `0.3.0` or `refs/tags/v0.3.0` as a semantic version.
+#[must_use]
+pub(crate) fn tag_version(tag: &str) -> Option<Version> {
+    let tag = tag.strip_prefix("refs/tags/").unwrap_or(tag);
+
+    Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// The release tags newer than `current`, oldest first. Pre-releases are only included when
+/// `current` is a pre-release as well.
+#[must_use]
+pub(crate) fn newer_tags<'a>(current: &str, tags: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let Some(current) = tag_version(current) else {
+        return vec![];
+    };
+
+    let mut newer = tags
+        .filter_map(|tag| tag_version(tag).map(|version| (version, tag)))
+        .filter(|(version, _)| *version > current)
+        .filter(|(version, _)| version.pre.is_empty() || !current.pre.is_empty())
+        .collect::<Vec<_>>();
+    newer.sort();
+
+    newer.into_iter().map(|(_, tag)| tag).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::newer_tags;
+
+    #[test]
+    fn test_newer_tags() {
+        let tags = [
+            "v0.2.0",
+            "v0.10.0",
+            "v0.3.0",
+            "v0.4.0-rc.1",
+            "v0.3.1",
+            "latest",
+        ];
+
+        assert_eq!(
+            newer_tags("refs/tags/v0.3.0", tags.into_iter()),
+            vec!["v0.3.1", "v0.10.0"]
+        );
+        assert_eq!(
+            newer_tags("v0.4.0-rc.0", tags.into_iter()),
+            vec!["v0.4.0-rc.1", "v0.10.0"]
+        );
+        assert_eq!(newer_tags("main", tags.into_iter()), Vec::<&str>::new());
+    }
+}
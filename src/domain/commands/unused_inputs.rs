@@ -0,0 +1,99 @@
+use std::path::Path;
+
+use crate::domain::{self, console::Console, nix::Flake, Result};
+
+use super::format::count;
+
+/// Lists the inputs of flake.nix the outputs function appears not to use, see
+/// [`crate::domain::nix::FlakeNix::unused_inputs`]. When `remove` is set their declarations are
+/// removed from flake.nix and the flake is relocked, otherwise the command fails with
+/// [`domain::Error::UnusedInputs`] if there's any.
+pub fn unused_inputs<F: Flake, C: Console>(
+    path: &Path,
+    remove: bool,
+    flake: &F,
+    console: &C,
+) -> Result<()> {
+    let mut flake_nix = flake.load_from(path)?;
+    let unused = flake_nix.unused_inputs()?;
+
+    if unused.is_empty() {
+        return console.println("every input is used by outputs");
+    }
+
+    for input in &unused {
+        console.println(format!("{input}: not used by outputs"))?;
+    }
+
+    if !remove {
+        return Err(domain::Error::UnusedInputs(unused));
+    }
+
+    for input in &unused {
+        flake_nix = flake_nix.without_input(input)?;
+    }
+    flake.write(path, &flake_nix)?;
+    flake.lock(path)?;
+
+    console.println(format!(
+        "removed {} from flake.nix",
+        count(unused.len(), "input")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{fixtures::TestFlake, Flake},
+    };
+
+    use super::unused_inputs;
+
+    const FLAKE_NIX: &str = r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane/v0.16.0";
+  inputs.flake-utils.url = "github:numtide/flake-utils";
+  outputs = { self, nixpkgs, ... }: { lib = nixpkgs.lib; };
+}"#;
+
+    #[test]
+    fn test_unused_inputs_reports_unused_inputs() {
+        let console = TestConsole::default();
+        let flake = TestFlake::default().with_flake_nix("flake", FLAKE_NIX);
+
+        let result = unused_inputs(&PathBuf::from("flake"), false, &flake, &console);
+
+        assert_eq!(result.unwrap_err().exit_code(), 6);
+        assert_eq!(
+            console.output(),
+            "crane: not used by outputs\nflake-utils: not used by outputs"
+        );
+        assert_eq!(flake.load_from("flake").unwrap().as_string(), FLAKE_NIX);
+    }
+
+    #[test]
+    fn test_unused_inputs_removes_unused_inputs() {
+        let console = TestConsole::default();
+        let flake = TestFlake::default().with_flake_nix("flake", FLAKE_NIX);
+
+        unused_inputs(&PathBuf::from("flake"), true, &flake, &console).unwrap();
+
+        assert_eq!(
+            flake.load_from("flake").unwrap().as_string(),
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  outputs = { self, nixpkgs, ... }: { lib = nixpkgs.lib; };
+}"#
+        );
+        assert_eq!(*flake.locked.borrow(), vec![PathBuf::from("flake")]);
+        assert_eq!(
+            console.output(),
+            "crane: not used by outputs\nflake-utils: not used by outputs\nremoved 2 inputs from flake.nix"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::Path;
+
+use crate::domain::{
+    self,
+    console::Console,
+    nix::{flake_url, Flake, FlakeLock, FlakeNix, InputReference},
+    Result,
+};
+
+use super::format::original;
+
+/// Checks that flake.lock matches flake.nix, e.g. after flake.nix was edited without relocking:
+/// every input flake.nix declares is locked, every input of the root of flake.lock is declared,
+/// the locked inputs were locked from the urls flake.nix declares, and follow the inputs
+/// flake.nix says they follow. Fails with [`domain::Error::Inconsistent`] if they don't match.
+pub fn verify<F: Flake, C: Console>(path: &Path, flake: &F, console: &C) -> Result<()> {
+    let flake_nix = flake.load_from(path)?;
+    let flake_lock = flake.load_lock_from(path)?;
+
+    let problems = problems(&flake_nix, &flake_lock)?;
+
+    if problems.is_empty() {
+        return console.println("flake.lock matches flake.nix");
+    }
+
+    for (input, problem) in &problems {
+        console.println(format!("{input}: {problem}"))?;
+    }
+
+    Err(domain::Error::Inconsistent(
+        problems.into_iter().map(|(input, _)| input).collect(),
+    ))
+}
+
+/// The inputs that don't match with what's wrong with them.
+fn problems(flake_nix: &FlakeNix, flake_lock: &FlakeLock) -> Result<Vec<(String, String)>> {
+    let mut declared = flake_nix.declared_inputs()?;
+    for input in flake_nix.outputs_inputs()? {
+        let path = vec![input];
+        if !declared.contains(&path) {
+            declared.push(path);
+        }
+    }
+
+    let mut problems = Vec::new();
+    for path in &declared {
+        let input = path.join("/");
+        let Some(reference) = input_reference(flake_lock, path) else {
+            problems.push((
+                input,
+                String::from("declared in flake.nix, missing from flake.lock"),
+            ));
+            continue;
+        };
+
+        if let Some(follows) = flake_nix.nested_input_attr(path, "follows") {
+            let follows_path = follows
+                .split('/')
+                .filter(|input| !input.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>();
+            match reference {
+                InputReference::Path(locked) if *locked == follows_path => {}
+                InputReference::Path(locked) => problems.push((
+                    input,
+                    format!(
+                        "follows {follows} in flake.nix, {} in flake.lock",
+                        locked.join("/")
+                    ),
+                )),
+                InputReference::Alias(_) => problems.push((
+                    input,
+                    format!("follows {follows} in flake.nix, has its own node in flake.lock"),
+                )),
+            }
+            continue;
+        }
+
+        let url = match &path[..] {
+            [input] => flake_nix.input_url(input).ok(),
+            _ => flake_nix.nested_input_attr(path, "url"),
+        };
+        let Some(url) = url else {
+            continue;
+        };
+        let Some(declared) = flake_url::original(&url) else {
+            log::debug!("{input}: {url} is not checked, the lock isn't modelled for it");
+            continue;
+        };
+
+        match reference {
+            InputReference::Alias(node_name) => {
+                let Some(node) = flake_lock.nodes.get(node_name) else {
+                    problems.push((
+                        input,
+                        format!("node {node_name} is missing from flake.lock"),
+                    ));
+                    continue;
+                };
+                let locked = &node.original;
+                if declared.source != locked.source
+                    || declared.r#ref != locked.r#ref
+                    || declared.rev != locked.rev
+                {
+                    problems.push((
+                        input,
+                        format!(
+                            "{} in flake.nix, {} in flake.lock",
+                            original(&declared),
+                            original(locked)
+                        ),
+                    ));
+                }
+            }
+            InputReference::Path(locked) => problems.push((
+                input,
+                format!(
+                    "{url} in flake.nix, follows {} in flake.lock",
+                    locked.join("/")
+                ),
+            )),
+        }
+    }
+
+    let mut orphans = flake_lock
+        .root
+        .inputs
+        .keys()
+        .filter(|input| !declared.contains(&vec![(*input).clone()]))
+        .collect::<Vec<_>>();
+    orphans.sort();
+    problems.extend(orphans.into_iter().map(|input| {
+        (
+            input.clone(),
+            String::from("locked in flake.lock, not declared in flake.nix"),
+        )
+    }));
+
+    Ok(problems)
+}
+
+/// The reference flake.lock locks the input at `path` with, `None` if it isn't locked.
+fn input_reference<'a>(flake_lock: &'a FlakeLock, path: &[String]) -> Option<&'a InputReference> {
+    let (input, parent) = path.split_last()?;
+    let inputs = if parent.is_empty() {
+        &flake_lock.root.inputs
+    } else {
+        &flake_lock
+            .nodes
+            .get(&flake_lock.resolve_path(parent)?)?
+            .inputs
+    };
+
+    inputs.get(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::domain::{
+        console::fixtures::TestConsole,
+        nix::{
+            fixtures::{crane_with_nixpkgs_copy, TestFlake},
+            InputReference,
+        },
+    };
+
+    use super::verify;
+
+    /// A flake locking crane at v0.16.0, crane's nixpkgs following the flake's nixpkgs.
+    fn flake(flake_nix: &str) -> TestFlake {
+        let mut flake_lock = crane_with_nixpkgs_copy();
+        flake_lock.nodes.remove("nixpkgs_2");
+        flake_lock.nodes.get_mut("crane").unwrap().inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Path(vec![String::from("nixpkgs")]),
+        );
+
+        TestFlake::default()
+            .with_lock("flake", flake_lock)
+            .with_flake_nix("flake", flake_nix)
+    }
+
+    #[test]
+    fn test_verify_matching_flake() {
+        let console = TestConsole::default();
+
+        verify(
+            &PathBuf::from("flake"),
+            &flake(
+                r#"{
+  inputs.crane.url = "github:ipetkov/crane/v0.16.0";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  outputs = { self, nixpkgs, crane }: {};
+}"#,
+            ),
+            &console,
+        )
+        .unwrap();
+
+        assert_eq!(console.output(), "flake.lock matches flake.nix");
+    }
+
+    #[test]
+    fn test_verify_reports_every_mismatch() {
+        let console = TestConsole::default();
+
+        let result = verify(
+            &PathBuf::from("flake"),
+            &flake(
+                r#"{
+  inputs.crane.url = "github:ipetkov/crane/v0.17.0";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs-stable";
+  inputs.flake-utils.url = "github:numtide/flake-utils";
+  outputs = { self, ... }: {};
+}"#,
+            ),
+            &console,
+        );
+
+        assert_eq!(
+            console.output(),
+            "crane: github:ipetkov/crane (ref v0.17.0) in flake.nix, github:ipetkov/crane (ref v0.16.0) in flake.lock
+crane/nixpkgs: follows nixpkgs-stable in flake.nix, nixpkgs in flake.lock
+flake-utils: declared in flake.nix, missing from flake.lock
+nixpkgs: locked in flake.lock, not declared in flake.nix"
+        );
+        assert_eq!(result.unwrap_err().exit_code(), 4);
+    }
+}
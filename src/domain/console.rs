@@ -3,3 +3,29 @@ use crate::domain;
 pub trait Console {
     fn println<S: AsRef<str>>(&self, s: S) -> domain::Result<()>;
 }
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::cell::RefCell;
+
+    use crate::domain;
+
+    /// A [`Console`](super::Console) that keeps everything printed in memory.
+    #[derive(Default)]
+    pub(crate) struct TestConsole {
+        pub(crate) lines: RefCell<Vec<String>>,
+    }
+
+    impl TestConsole {
+        pub(crate) fn output(&self) -> String {
+            self.lines.borrow().join("\n")
+        }
+    }
+
+    impl super::Console for TestConsole {
+        fn println<S: AsRef<str>>(&self, s: S) -> domain::Result<()> {
+            self.lines.borrow_mut().push(String::from(s.as_ref()));
+            Ok(())
+        }
+    }
+}
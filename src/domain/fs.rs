@@ -8,4 +8,8 @@ pub trait FileSystem {
     fn write<P: AsRef<Path>>(&self, p: P, str: &str) -> Result<()>;
 
     fn current_dir(&self) -> Result<PathBuf>;
+
+    /// Recursively finds every file called one of `file_names` under `root`, respecting
+    /// `.gitignore` files and skipping `.direnv` directories and `result` symlinks.
+    fn find_files<P: AsRef<Path>>(&self, root: P, file_names: &[&str]) -> Result<Vec<PathBuf>>;
 }
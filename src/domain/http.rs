@@ -0,0 +1,13 @@
+use crate::domain::Result;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+pub trait HttpClient {
+    /// Sends a GET request, responses with an error status are returned as well, only transport
+    /// errors (e.g. being offline) fail.
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpResponse>;
+}
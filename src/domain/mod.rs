@@ -1,7 +1,11 @@
 pub mod commands;
 pub mod console;
 pub mod fs;
+pub mod http;
 pub mod nix;
+pub mod process;
+pub mod remote;
+pub mod revision;
 
 use std::io;
 
@@ -15,10 +19,40 @@ pub enum Error {
     InvalidFlakeLock { reason: String },
     #[error("sync error: {:?}", .0)]
     SyncError(String),
+    #[error("process error: {}", .0)]
+    ProcessError(String),
+    #[error("http error: {}", .0)]
+    HttpError(String),
     #[error("nix parser error: {:?}", .0)]
     NixParserError(String),
+    #[error("inputs are out of sync: {}", .0.join(", "))]
+    OutOfSync(Vec<String>),
+    #[error("inputs are older than allowed: {}", .0.join(", "))]
+    StaleInputs(Vec<String>),
+    #[error("flake.lock doesn't match flake.nix: {}", .0.join(", "))]
+    Inconsistent(Vec<String>),
+    #[error("flake.lock has nodes unreachable from its root: {}", .0.join(", "))]
+    UnreachableNodes(Vec<String>),
+    #[error("inputs are not used by outputs: {}", .0.join(", "))]
+    UnusedInputs(Vec<String>),
     #[error("an error happened: {:?}", .0)]
     Error(String),
 }
 
+impl Error {
+    /// The exit code of the process when it fails with this error, failed checks have their own
+    /// dedicated exit code.
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::OutOfSync(_) => 2,
+            Error::StaleInputs(_) => 3,
+            Error::Inconsistent(_) => 4,
+            Error::UnreachableNodes(_) => 5,
+            Error::UnusedInputs(_) => 6,
+            _ => 1,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
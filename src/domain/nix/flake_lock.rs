@@ -1,30 +1,177 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use nova::newtype;
 use time::OffsetDateTime;
 
+#[derive(Clone)]
 pub struct FlakeLock {
     pub root: RootNode,
     pub nodes: HashMap<String, Node>,
 }
 
 impl FlakeLock {
+    /// Returns the nodes of the root inputs keyed by the input's name, following the references
+    /// of inputs that follow another input.
     #[must_use]
     pub fn input_nodes(&self) -> HashMap<String, Node> {
-        todo!()
+        self.root
+            .inputs
+            .iter()
+            .filter_map(|(name, reference)| {
+                self.resolve_reference(reference)
+                    .and_then(|node_name| self.nodes.get(&node_name))
+                    .map(|node| (name.clone(), node.clone()))
+            })
+            .collect()
+    }
+
+    /// Walks the lock graph depth first starting from the root, children are visited in the order
+    /// of their name. Inputs that follow another input are returned as [`LockEdgeTarget::Follows`]
+    /// and aren't walked further, so every node appears under the input that brings it.
+    #[must_use]
+    pub fn edges(&self) -> Vec<LockEdge<'_>> {
+        let mut edges = Vec::new();
+        self.walk(
+            &self.root.inputs,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut edges,
+        );
+        edges
+    }
+
+    /// Returns the input paths bringing each node into the lock graph keyed by the node's name,
+    /// in the order of [`FlakeLock::edges`]. Inputs following another input don't bring a node.
+    #[must_use]
+    pub fn node_paths(&self) -> HashMap<&str, Vec<Vec<String>>> {
+        let mut paths = HashMap::<&str, Vec<Vec<String>>>::new();
+        for edge in self.edges() {
+            if let LockEdgeTarget::Node { node_name, .. } = edge.target {
+                paths.entry(node_name).or_default().push(edge.path);
+            }
+        }
+        paths
+    }
+
+    /// Returns the names of the nodes reachable from the root when the nodes in `redirects` are
+    /// replaced by the node they're mapped to, e.g. to tell which nodes a follows would remove.
+    #[must_use]
+    pub fn reachable(&self, redirects: &HashMap<&str, &str>) -> HashSet<String> {
+        let resolve = |reference: &InputReference| {
+            let name = self.resolve_reference(reference)?;
+            Some(
+                redirects
+                    .get(name.as_str())
+                    .map_or(name, |redirect| String::from(*redirect)),
+            )
+        };
+
+        let mut visited = HashSet::new();
+        let mut queue = self
+            .root
+            .inputs
+            .values()
+            .filter_map(resolve)
+            .collect::<Vec<_>>();
+        while let Some(name) = queue.pop() {
+            if let Some(node) = self.nodes.get(&name) {
+                if visited.insert(name) {
+                    queue.extend(node.inputs.values().filter_map(resolve));
+                }
+            }
+        }
+
+        visited
+    }
+
+    fn walk<'a>(
+        &'a self,
+        inputs: &'a HashMap<String, InputReference>,
+        path: &mut Vec<String>,
+        visiting: &mut Vec<&'a str>,
+        edges: &mut Vec<LockEdge<'a>>,
+    ) {
+        let mut names = inputs.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            path.push(name.clone());
+
+            match &inputs[name] {
+                InputReference::Alias(node_name) => {
+                    if let Some(node) = self.nodes.get(node_name) {
+                        edges.push(LockEdge {
+                            path: path.clone(),
+                            target: LockEdgeTarget::Node { node_name, node },
+                        });
+
+                        if !visiting.contains(&node_name.as_str()) {
+                            visiting.push(node_name);
+                            self.walk(&node.inputs, path, visiting, edges);
+                            visiting.pop();
+                        }
+                    }
+                }
+                InputReference::Path(follows) => edges.push(LockEdge {
+                    path: path.clone(),
+                    target: LockEdgeTarget::Follows(follows),
+                }),
+            }
+
+            path.pop();
+        }
+    }
+
+    fn resolve_reference(&self, reference: &InputReference) -> Option<String> {
+        match reference {
+            InputReference::Alias(node_name) => Some(node_name.clone()),
+            InputReference::Path(path) => self.resolve_path(path),
+        }
     }
+
+    /// Resolves the name of the node an input path refers to, e.g. the `follows` path
+    /// `["crane", "nixpkgs"]`.
+    #[must_use]
+    pub fn resolve_path(&self, path: &[String]) -> Option<String> {
+        let (first, rest) = path.split_first()?;
+        let node_name = self.resolve_reference(self.root.inputs.get(first)?)?;
+
+        rest.iter().try_fold(node_name, |node_name, input| {
+            self.resolve_reference(self.nodes.get(&node_name)?.inputs.get(input)?)
+        })
+    }
+}
+
+/// An input of a node in the lock graph, identified by the path of input names leading to it from
+/// the root.
+#[derive(Debug)]
+pub struct LockEdge<'a> {
+    pub path: Vec<String>,
+    pub target: LockEdgeTarget<'a>,
 }
 
+#[derive(Debug)]
+pub enum LockEdgeTarget<'a> {
+    Node { node_name: &'a str, node: &'a Node },
+    Follows(&'a [String]),
+}
+
+#[derive(Clone)]
 pub struct RootNode {
     pub inputs: HashMap<String, InputReference>,
 }
 
+#[derive(Clone, Debug)]
 pub struct Node {
     pub inputs: HashMap<String, InputReference>,
     pub locked: Locked,
     pub original: Original,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum InputReference {
     Alias(String),
     Path(Vec<String>),
@@ -48,7 +195,7 @@ impl From<&str> for LockedRef {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Locked {
     pub rev: LockedRev,
     pub r#ref: Option<LockedRef>,
@@ -56,13 +203,77 @@ pub struct Locked {
     pub last_modified: OffsetDateTime,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LockedSource {
     GitHub { owner: String, repo: String },
     GitLab { owner: String, repo: String },
     Git { url: String },
 }
 
+impl LockedSource {
+    /// The name of the fetcher nix uses for this source.
+    #[must_use]
+    pub fn fetcher(&self) -> &'static str {
+        match self {
+            Self::GitHub { .. } => "github",
+            Self::GitLab { .. } => "gitlab",
+            Self::Git { .. } => "git",
+        }
+    }
+
+    /// The url the repository of the source can be cloned from.
+    #[must_use]
+    pub fn git_url(&self) -> String {
+        match self {
+            Self::GitHub { owner, repo } => format!("https://github.com/{owner}/{repo}.git"),
+            Self::GitLab { owner, repo } => format!("https://gitlab.com/{owner}/{repo}.git"),
+            Self::Git { url } => url.clone(),
+        }
+    }
+
+    /// The repository of the source: `owner/repo` for forges, the url for git.
+    #[must_use]
+    pub fn repository(&self) -> String {
+        match self {
+            Self::GitHub { owner, repo } | Self::GitLab { owner, repo } => {
+                format!("{owner}/{repo}")
+            }
+            Self::Git { url } => url.clone(),
+        }
+    }
+
+    /// Identifies the repository regardless of how it's fetched, e.g. `github:NixOS/nixpkgs`
+    /// and `git+https://github.com/nixos/nixpkgs.git` are both `github:nixos/nixpkgs`.
+    #[must_use]
+    pub fn normalized(&self) -> String {
+        let source = match self {
+            Self::Git { url } => {
+                let url = url.trim_end_matches('/').trim_end_matches(".git");
+                if let Some(repository) = url.strip_prefix("https://github.com/") {
+                    format!("github:{repository}")
+                } else if let Some(repository) = url.strip_prefix("https://gitlab.com/") {
+                    format!("gitlab:{repository}")
+                } else {
+                    format!("git+{url}")
+                }
+            }
+            _ => self.to_string(),
+        };
+
+        source.to_lowercase()
+    }
+}
+
+impl fmt::Display for LockedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GitHub { owner, repo } => write!(f, "github:{owner}/{repo}"),
+            Self::GitLab { owner, repo } => write!(f, "gitlab:{owner}/{repo}"),
+            Self::Git { url } => write!(f, "git+{url}"),
+        }
+    }
+}
+
 #[newtype(new, serde, borrow = "str")]
 pub type OriginalRev = String;
 
@@ -96,19 +307,165 @@ pub enum OriginalSource {
     Indirect { id: String },
 }
 
+impl fmt::Display for OriginalSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GitHub { owner, repo } => write!(f, "github:{owner}/{repo}"),
+            Self::GitLab { owner, repo } => write!(f, "gitlab:{owner}/{repo}"),
+            Self::Git { url } => write!(f, "git+{url}"),
+            Self::Indirect { id } => write!(f, "{id}"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        fixtures::{crane_with_nixpkgs_copy, flake_lock_with_node, git_node_with_url_only},
+        InputReference, LockEdgeTarget, LockedRev, LockedSource,
+    };
+
+    #[test]
+    fn test_locked_source_normalized() {
+        let github = LockedSource::GitHub {
+            owner: String::from("NixOS"),
+            repo: String::from("nixpkgs"),
+        };
+        let git = LockedSource::Git {
+            url: String::from("https://github.com/nixos/nixpkgs.git"),
+        };
+        let other = LockedSource::Git {
+            url: String::from("https://git.vdx.hu/voidcontext/nix-rust-utils.git"),
+        };
+
+        assert_eq!(github.normalized(), "github:nixos/nixpkgs");
+        assert_eq!(git.normalized(), "github:nixos/nixpkgs");
+        assert_eq!(
+            other.normalized(),
+            "git+https://git.vdx.hu/voidcontext/nix-rust-utils"
+        );
+    }
+
+    #[test]
+    fn test_flake_lock_input_nodes() {
+        let rev = LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd");
+        let mut flake_lock = flake_lock_with_node(
+            "nix-rust-utils",
+            git_node_with_url_only("https://git.vdx.hu/voidcontext/nix-rust-utils.git", &rev),
+        );
+        flake_lock.root.inputs.insert(
+            String::from("nixpkgs"),
+            InputReference::Path(vec![
+                String::from("nix-rust-utils"),
+                String::from("nixpgks"),
+            ]),
+        );
+        flake_lock
+            .nodes
+            .get_mut("nix-rust-utils")
+            .unwrap()
+            .inputs
+            .insert(
+                String::from("nixpgks"),
+                InputReference::Alias(String::from("nixpkgs")),
+            );
+
+        let input_nodes = flake_lock.input_nodes();
+        let mut names = input_nodes.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+
+        assert_eq!(names, vec!["nix-rust-utils", "nixpkgs"]);
+        assert_eq!(&*input_nodes["nix-rust-utils"].locked.rev, &*rev);
+        assert_eq!(
+            &*input_nodes["nixpkgs"].locked.rev,
+            "a08e061a4ee8329747d54ddf1566d34c55c895eb"
+        );
+    }
+
+    #[test]
+    fn test_flake_lock_edges() {
+        let rev = LockedRev::from("3892194d7b3293de8b30f1d19e2af45ba41ba8fd");
+        let mut node =
+            git_node_with_url_only("https://git.vdx.hu/voidcontext/nix-rust-utils.git", &rev);
+        node.inputs.insert(
+            String::from("nixpgks"),
+            InputReference::Alias(String::from("nixpkgs")),
+        );
+        node.inputs.insert(
+            String::from("flake-utils"),
+            InputReference::Path(vec![String::from("flake-utils")]),
+        );
+        let flake_lock = flake_lock_with_node("nix-rust-utils", node);
+
+        let edges = flake_lock
+            .edges()
+            .into_iter()
+            .map(|edge| {
+                (
+                    edge.path.join("/"),
+                    match edge.target {
+                        LockEdgeTarget::Node { node_name, .. } => String::from(node_name),
+                        LockEdgeTarget::Follows(path) => format!("follows {}", path.join("/")),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            edges,
+            vec![
+                (
+                    String::from("nix-rust-utils"),
+                    String::from("nix-rust-utils")
+                ),
+                (
+                    String::from("nix-rust-utils/flake-utils"),
+                    String::from("follows flake-utils")
+                ),
+                (
+                    String::from("nix-rust-utils/nixpgks"),
+                    String::from("nixpkgs")
+                ),
+            ]
+        );
+    }
+
     #[test]
-    #[ignore = "Not implemented"]
-    fn test_flake_lock_input_nodes() {}
+    fn test_flake_lock_reachable() {
+        let flake_lock = crane_with_nixpkgs_copy();
+        let sorted = |reachable: HashSet<String>| {
+            let mut reachable = reachable.into_iter().collect::<Vec<_>>();
+            reachable.sort();
+            reachable
+        };
+
+        assert_eq!(
+            sorted(flake_lock.reachable(&HashMap::new())),
+            vec!["crane", "nixpkgs", "nixpkgs_2"]
+        );
+        assert_eq!(
+            sorted(flake_lock.reachable(&HashMap::from([("nixpkgs_2", "nixpkgs")]))),
+            vec!["crane", "nixpkgs"]
+        );
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod fixtures {
-    use std::collections::HashMap;
+    use std::{
+        cell::RefCell,
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
 
     use time::OffsetDateTime;
 
+    use crate::domain::{nix::FlakeNix, Error, Result};
+
     use super::{
         FlakeLock, InputReference, Locked, LockedRef, LockedRev, LockedSource, Node, Original,
         OriginalRef, OriginalRev, OriginalSource, RootNode,
@@ -297,6 +654,114 @@ pub(crate) mod fixtures {
         }
     }
 
+    /// An in-memory [`Flake`](crate::domain::nix::Flake), the current flake is at `.`.
+    #[derive(Default)]
+    pub(crate) struct TestFlake {
+        pub(crate) flake_locks: HashMap<PathBuf, FlakeLock>,
+        pub(crate) flake_nixes: RefCell<HashMap<PathBuf, String>>,
+        /// The locks the flakes have once [`crate::domain::nix::Flake::lock`] is called
+        pub(crate) relocked: HashMap<PathBuf, FlakeLock>,
+        pub(crate) locked: RefCell<Vec<PathBuf>>,
+        /// The nodes [`crate::domain::nix::Flake::remove_lock_nodes`] removed from the locks
+        pub(crate) removed_nodes: RefCell<HashMap<PathBuf, Vec<String>>>,
+    }
+
+    impl TestFlake {
+        pub(crate) fn with_lock<P: AsRef<Path>>(mut self, p: P, flake_lock: FlakeLock) -> Self {
+            self.flake_locks
+                .insert(p.as_ref().to_path_buf(), flake_lock);
+            self
+        }
+
+        pub(crate) fn with_relocked<P: AsRef<Path>>(mut self, p: P, flake_lock: FlakeLock) -> Self {
+            self.relocked.insert(p.as_ref().to_path_buf(), flake_lock);
+            self
+        }
+
+        pub(crate) fn with_flake_nix<P: AsRef<Path>>(self, p: P, flake_nix: &str) -> Self {
+            self.flake_nixes
+                .borrow_mut()
+                .insert(p.as_ref().to_path_buf(), String::from(flake_nix));
+            self
+        }
+    }
+
+    impl crate::domain::nix::Flake for TestFlake {
+        fn load_lock(&self) -> Result<FlakeLock> {
+            self.load_lock_from(".")
+        }
+
+        fn load_lock_from<P: AsRef<Path>>(&self, p: P) -> Result<FlakeLock> {
+            let relocked = self
+                .locked
+                .borrow()
+                .contains(&p.as_ref().to_path_buf())
+                .then(|| self.relocked.get(p.as_ref()))
+                .flatten();
+
+            let mut flake_lock = relocked
+                .or_else(|| self.flake_locks.get(p.as_ref()))
+                .cloned()
+                .ok_or_else(|| Error::Error(format!("no flake.lock at {:?}", p.as_ref())))?;
+            for node in self
+                .removed_nodes
+                .borrow()
+                .get(p.as_ref())
+                .into_iter()
+                .flatten()
+            {
+                flake_lock.nodes.remove(node);
+            }
+
+            Ok(flake_lock)
+        }
+
+        fn load_from<P: AsRef<Path>>(&self, p: P) -> Result<FlakeNix> {
+            self.flake_nixes
+                .borrow()
+                .get(p.as_ref())
+                .cloned()
+                .map(FlakeNix::new)
+                .ok_or_else(|| Error::Error(format!("no flake.nix at {:?}", p.as_ref())))
+        }
+
+        fn discover<P: AsRef<Path>>(&self, root: P) -> Result<Vec<PathBuf>> {
+            let mut flakes = self
+                .flake_locks
+                .keys()
+                .filter(|p| p.starts_with(root.as_ref()))
+                .cloned()
+                .collect::<Vec<_>>();
+            flakes.sort();
+            Ok(flakes)
+        }
+
+        fn write<P: AsRef<Path>>(&self, p: P, flake: &FlakeNix) -> Result<()> {
+            self.flake_nixes
+                .borrow_mut()
+                .insert(p.as_ref().to_path_buf(), flake.as_string());
+            Ok(())
+        }
+
+        fn override_input<P: AsRef<Path>>(&self, _p: P, _input: &str, _url: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn lock<P: AsRef<Path>>(&self, p: P) -> Result<()> {
+            self.locked.borrow_mut().push(p.as_ref().to_path_buf());
+            Ok(())
+        }
+
+        fn remove_lock_nodes<P: AsRef<Path>>(&self, p: P, nodes: &[String]) -> Result<()> {
+            self.removed_nodes
+                .borrow_mut()
+                .entry(p.as_ref().to_path_buf())
+                .or_default()
+                .extend(nodes.iter().cloned());
+            Ok(())
+        }
+    }
+
     #[must_use]
     pub(crate) fn flake_lock_with_node(name: &str, node: Node) -> FlakeLock {
         let mut nodes = HashMap::new();
@@ -322,4 +787,31 @@ pub(crate) mod fixtures {
             root: root_node("nix-rust-utils"),
         }
     }
+
+    /// The lock of a flake with `crane` and `nixpkgs` inputs. `crane` is locked at v0.16.0 and
+    /// brings its own copy of nixpkgs, `nixpkgs_2`, locked at another revision.
+    #[must_use]
+    pub(crate) fn crane_with_nixpkgs_copy() -> FlakeLock {
+        let mut crane = github_node_with_ref(
+            "ipetkov",
+            "crane",
+            &LockedRev::from("8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1"),
+            &OriginalRef::from("v0.16.0"),
+        );
+        crane.inputs = HashMap::from([(
+            String::from("nixpkgs"),
+            InputReference::Alias(String::from("nixpkgs_2")),
+        )]);
+        let mut flake_lock = flake_lock_with_node("crane", crane);
+        flake_lock.root.inputs = inputs("crane");
+        flake_lock.root.inputs.extend(inputs("nixpkgs"));
+
+        let mut nixpkgs_2 = flake_lock.nodes["nixpkgs"].clone();
+        nixpkgs_2.locked.rev = LockedRev::from("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad");
+        flake_lock
+            .nodes
+            .insert(String::from("nixpkgs_2"), nixpkgs_2);
+
+        flake_lock
+    }
 }
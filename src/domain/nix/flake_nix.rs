@@ -1,3 +1,20 @@
+use std::{cmp::Reverse, collections::HashSet, ops::Range};
+
+use rnix::{
+    ast::{self, Attr, AttrpathValue, Entry, HasEntry, InterpolPart},
+    Root,
+    SyntaxKind::{
+        NODE_ATTRPATH, NODE_IDENT_PARAM, NODE_INHERIT, NODE_INHERIT_FROM, NODE_PAT_BIND,
+        NODE_PAT_ENTRY,
+    },
+};
+use rowan::{
+    ast::{AstChildren, AstNode},
+    GreenNode, GreenNodeBuilder, TextRange,
+};
+
+use crate::domain::{Error, Result};
+
 pub struct FlakeNix {
     as_string: String,
 }
@@ -10,4 +27,882 @@ impl FlakeNix {
     pub(crate) fn as_string(&self) -> String {
         self.as_string.clone()
     }
+
+    /// The url of the given input.
+    pub(crate) fn input_url(&self, input: &str) -> Result<String> {
+        let root = self.parse()?;
+
+        string_content(&find_input_url(input, &root)?).ok_or_else(|| {
+            Error::NixParserError(format!("Couldn't find the url of {input} in flake.nix"))
+        })
+    }
+
+    /// The value flake.nix sets the attribute of an input of an input to, e.g. the `url` or
+    /// `follows` of `["crane", "nixpkgs"]` set by `inputs.crane.inputs.nixpkgs.url = ...;`.
+    /// `None` when flake.nix doesn't set it.
+    pub(crate) fn nested_input_attr(&self, input_path: &[String], attr: &str) -> Option<String> {
+        let root = self.parse().ok()?;
+
+        string_content(&find_nested_input_attr(input_path, attr, &root).ok()?)
+    }
+
+    /// The input paths flake.nix declares in the order of their declarations, e.g. `["crane"]`
+    /// and `["crane", "nixpkgs"]` for `inputs.crane.inputs.nixpkgs.follows = "nixpkgs";`.
+    pub(crate) fn declared_inputs(&self) -> Result<Vec<Vec<String>>> {
+        declared_input_paths(&self.parse()?)
+    }
+
+    /// The inputs the outputs function takes, e.g. `nixpkgs` for
+    /// `outputs = { self, nixpkgs }: ...;`. Inputs that are only taken here are looked up in
+    /// the flake registry.
+    pub(crate) fn outputs_inputs(&self) -> Result<Vec<String>> {
+        Ok(outputs_args(&self.parse()?)?
+            .into_iter()
+            .filter(|arg| arg != "self")
+            .collect())
+    }
+
+    /// Replaces the url of the given input, keeping the rest of the file untouched.
+    pub(crate) fn with_input_url(&self, input: &str, url: &str) -> Result<FlakeNix> {
+        let root = self.parse()?;
+        let input_url = find_input_url(input, &root)?;
+
+        Ok(FlakeNix::new(
+            override_input_url(&input_url, url).to_string(),
+        ))
+    }
+
+    /// Declares that the input at `path`, e.g. `["crane", "nixpkgs"]`, follows the input at
+    /// `follows`. The declaration is inserted after the url of the flake's input, e.g.
+    /// `inputs.crane.inputs.nixpkgs.follows = "nixpkgs";` after `inputs.crane.url = ...;`.
+    pub(crate) fn with_follows(&self, path: &[String], follows: &str) -> Result<FlakeNix> {
+        let Some((input, nested @ [_, ..])) = path.split_first() else {
+            return Err(Error::Error(format!(
+                "{} is an input of the flake, only the inputs of inputs can follow",
+                path.join("/")
+            )));
+        };
+        let root = self.parse()?;
+        let url = find_input_url(input, &root)?;
+
+        // the url is declared as e.g. `inputs.crane.url`, `crane.url` or `url`
+        let mut attrs = url
+            .attrpath()
+            .map(|attrpath| {
+                attrpath
+                    .attrs()
+                    .map(|attr| attr.syntax().text().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        attrs.pop();
+        for input in nested {
+            attrs.push(String::from("inputs"));
+            attrs.push(attr_name(input));
+        }
+        attrs.push(String::from("follows"));
+        let declaration = format!("{} = \"{follows}\";", attrs.join("."));
+
+        let start = usize::from(url.syntax().text_range().start());
+        let end = usize::from(url.syntax().text_range().end());
+        let line_start = self.as_string[..start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let indent = self.as_string[line_start..start]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect::<String>();
+
+        Ok(FlakeNix::new(format!(
+            "{}\n{indent}{declaration}{}",
+            &self.as_string[..end],
+            &self.as_string[end..]
+        )))
+    }
+
+    /// The inputs the outputs function appears not to use: the ones it neither takes by name
+    /// and refers to, nor accesses through the attribute set of every input, e.g. `inputs.crane`
+    /// for `{ self, ... }@inputs:`, and that no other input follows. Nothing is reported when
+    /// that attribute set is used as a whole, e.g. passed to another function, as any input
+    /// could be used then.
+    pub(crate) fn unused_inputs(&self) -> Result<Vec<String>> {
+        let Some(lambda) = find_outputs_lambda(&self.parse()?)? else {
+            return Ok(vec![]);
+        };
+        let usage = OutputsUsage::new(&lambda);
+        if usage.binding_escapes {
+            return Ok(vec![]);
+        }
+
+        let declared = self.declared_inputs()?;
+        let followed = declared
+            .iter()
+            .filter_map(|path| self.nested_input_attr(path, "follows"))
+            .filter_map(|follows| follows.split('/').next().map(String::from))
+            .collect::<HashSet<_>>();
+        let mut inputs = declared
+            .into_iter()
+            .filter_map(|path| match &path[..] {
+                [input] => Some(input.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for arg in &usage.args {
+            if arg != "self" && !inputs.contains(arg) {
+                inputs.push(arg.clone());
+            }
+        }
+
+        Ok(inputs
+            .into_iter()
+            .filter(|input| !usage.uses(input) && !followed.contains(input))
+            .collect())
+    }
+
+    /// Removes every declaration of the given input, e.g. `inputs.crane.url = ...;` or
+    /// `crane.inputs.nixpkgs.follows = ...;` in `inputs = { ... };`, and the argument of the
+    /// outputs function taking it. Declarations alone on their lines are removed with their lines.
+    /// Inputs only taken by the outputs function, e.g. registry inputs, lose their argument alone.
+    pub(crate) fn without_input(&self, input: &str) -> Result<FlakeNix> {
+        let root = self.parse()?;
+        let Some(ast::Expr::AttrSet(set)) = root.expr() else {
+            return Err(Error::NixParserError(String::from("root isn't a set")));
+        };
+
+        let mut ranges = Vec::new();
+        for entry in set.entries() {
+            let ast::Entry::AttrpathValue(entry) = entry else {
+                continue;
+            };
+            match &attr_names(&entry)[..] {
+                [inputs, name, ..] if inputs == "inputs" && name == input => {
+                    ranges.push(self.line_range(entry.syntax().text_range()));
+                }
+                [inputs] if inputs == "inputs" => {
+                    let Some(ast::Expr::AttrSet(inputs)) = entry.value() else {
+                        continue;
+                    };
+                    for nested in inputs.entries() {
+                        let ast::Entry::AttrpathValue(nested) = nested else {
+                            continue;
+                        };
+                        if attr_names(&nested).first().map(String::as_str) == Some(input) {
+                            ranges.push(self.line_range(nested.syntax().text_range()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(ast::Param::Pattern(pattern)) =
+            find_outputs_lambda(&root)?.and_then(|lambda| lambda.param())
+        {
+            ranges.extend(
+                pattern
+                    .pat_entries()
+                    .filter(|entry| {
+                        entry
+                            .ident()
+                            .and_then(|ident| ident_text(&ident))
+                            .as_deref()
+                            == Some(input)
+                    })
+                    .map(|entry| self.pattern_entry_range(entry.syntax().text_range())),
+            );
+        }
+        if ranges.is_empty() {
+            return Err(Error::Error(format!(
+                "{input} is neither declared nor taken by the outputs in flake.nix"
+            )));
+        }
+
+        ranges.sort_by_key(|range| Reverse(range.start));
+        let mut content = self.as_string.clone();
+        for range in ranges {
+            content.replace_range(range, "");
+        }
+
+        Ok(FlakeNix::new(content))
+    }
+
+    /// The whole line of the text range when nothing else is on it, the text range and the
+    /// spaces following it otherwise.
+    fn line_range(&self, range: TextRange) -> Range<usize> {
+        let (start, end) = (usize::from(range.start()), usize::from(range.end()));
+        let line_start = self.as_string[..start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_end = self.as_string[end..]
+            .find('\n')
+            .map_or(self.as_string.len(), |index| end + index + 1);
+
+        if self.as_string[line_start..start].trim().is_empty()
+            && self.as_string[end..line_end].trim().is_empty()
+        {
+            line_start..line_end
+        } else {
+            start..end + self.spaces_from(end)
+        }
+    }
+
+    /// The text range of an argument of a function and the comma separating it from the next
+    /// argument, or from the previous one if it's the last argument.
+    fn pattern_entry_range(&self, range: TextRange) -> Range<usize> {
+        let (start, end) = (usize::from(range.start()), usize::from(range.end()));
+        let after = end + self.spaces_from(end);
+
+        if self.as_string[after..].starts_with(',') {
+            start..after + 1 + self.spaces_from(after + 1)
+        } else {
+            let before = self.as_string[..start].trim_end();
+            if before.ends_with(',') {
+                before.len() - 1..end
+            } else {
+                start..end
+            }
+        }
+    }
+
+    fn spaces_from(&self, index: usize) -> usize {
+        self.as_string[index..]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(char::len_utf8)
+            .sum()
+    }
+
+    fn parse(&self) -> Result<rnix::Root> {
+        rnix::Root::parse(&self.as_string)
+            .ok()
+            .map_err(|err| Error::NixParserError(err.to_string()))
+    }
+}
+
+/// What the body of the outputs function refers to.
+#[derive(Default)]
+struct OutputsUsage {
+    /// The inputs the function takes by name
+    args: Vec<String>,
+    /// The name of the attribute set of every input, e.g. `inputs` for `{ self, ... }@inputs:`
+    /// or `inputs: ...`
+    binding: Option<String>,
+    /// The variables the body refers to
+    referenced: HashSet<String>,
+    /// The inputs the body accesses through the binding or `self.inputs`
+    accessed: HashSet<String>,
+    /// Whether the body uses the binding other than to access one of the inputs
+    binding_escapes: bool,
+}
+
+impl OutputsUsage {
+    fn new(lambda: &ast::Lambda) -> Self {
+        let mut usage = Self::default();
+        match lambda.param() {
+            Some(ast::Param::Pattern(pattern)) => {
+                usage.args = pattern
+                    .pat_entries()
+                    .filter_map(|entry| entry.ident())
+                    .filter_map(|ident| ident_text(&ident))
+                    .collect();
+                usage.binding = pattern
+                    .pat_bind()
+                    .and_then(|bind| bind.ident())
+                    .and_then(|ident| ident_text(&ident));
+            }
+            Some(ast::Param::IdentParam(param)) => {
+                usage.binding = param.ident().and_then(|ident| ident_text(&ident));
+            }
+            None => {}
+        }
+
+        let Some(body) = lambda.body() else {
+            return usage;
+        };
+        for node in body.syntax().descendants() {
+            if let Some(inherit) = ast::Inherit::cast(node.clone()) {
+                let names = inherit.attrs().filter_map(|attr| attr_text(&attr));
+                match inherit.from().and_then(|from| from.expr()) {
+                    None => usage.referenced.extend(names),
+                    Some(ast::Expr::Ident(ident)) if ident_text(&ident) == usage.binding => {
+                        usage.accessed.extend(names);
+                    }
+                    Some(_) => {}
+                }
+            } else if let Some(ident) = ast::Ident::cast(node) {
+                usage.visit(&ident);
+            }
+        }
+
+        usage
+    }
+
+    fn visit(&mut self, ident: &ast::Ident) {
+        let Some(name) = ident_text(ident) else {
+            return;
+        };
+        let parent = ident.syntax().parent();
+        let is_binding = self.binding.as_ref() == Some(&name);
+
+        match parent.as_ref().map(rowan::SyntaxNode::kind) {
+            // names of attributes and arguments of nested functions aren't references
+            Some(
+                NODE_ATTRPATH | NODE_INHERIT | NODE_PAT_ENTRY | NODE_PAT_BIND | NODE_IDENT_PARAM,
+            ) => return,
+            // `inherit (inputs) crane;` is handled with the inherited names
+            Some(NODE_INHERIT_FROM) if is_binding => return,
+            _ => {}
+        }
+
+        let attrs = parent
+            .and_then(ast::Select::cast)
+            .filter(|select| {
+                select
+                    .expr()
+                    .is_some_and(|expr| expr.syntax() == ident.syntax())
+            })
+            .and_then(|select| select.attrpath())
+            .map_or_else(Vec::new, |attrpath| {
+                attrpath.attrs().map(|attr| attr_text(&attr)).collect()
+            });
+
+        if is_binding {
+            match attrs.first() {
+                Some(Some(input)) => {
+                    self.accessed.insert(input.clone());
+                }
+                _ => self.binding_escapes = true,
+            }
+        } else if name == "self" {
+            if let [Some(inputs), Some(input), ..] = &attrs[..] {
+                if inputs == "inputs" {
+                    self.accessed.insert(input.clone());
+                }
+            }
+        }
+        self.referenced.insert(name);
+    }
+
+    fn uses(&self, input: &str) -> bool {
+        let taken = self.args.iter().any(|arg| arg == input);
+
+        (taken && self.referenced.contains(input)) || self.accessed.contains(input)
+    }
+}
+
+fn ident_text(ident: &ast::Ident) -> Option<String> {
+    ident.ident_token().map(|token| token.text().to_string())
+}
+
+/// The name of the attribute, `None` when it's computed, e.g. `${name}`.
+fn attr_text(attr: &Attr) -> Option<String> {
+    match attr {
+        Attr::Ident(ident) => ident_text(ident),
+        Attr::Str(str) => match &str.normalized_parts()[..] {
+            [InterpolPart::Literal(literal)] => Some(literal.clone()),
+            _ => None,
+        },
+        Attr::Dynamic(_) => None,
+    }
+}
+
+fn attr_names(entry: &ast::AttrpathValue) -> Vec<String> {
+    entry
+        .attrpath()
+        .into_iter()
+        .flat_map(|attrpath| attrpath.attrs())
+        .filter_map(|attr| attr_text(&attr))
+        .collect()
+}
+
+/// Quotes input names that aren't valid identifiers.
+fn attr_name(name: &str) -> String {
+    let is_ident = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''));
+
+    if is_ident {
+        String::from(name)
+    } else {
+        format!("\"{name}\"")
+    }
+}
+
+pub(super) fn find_input_url(input: &str, flake: &Root) -> Result<AttrpathValue> {
+    let expr = flake.expr().unwrap();
+    let set = match expr {
+        ast::Expr::AttrSet(set) => Ok(set),
+        _ => Err(Error::NixParserError(String::from("root isn't a set"))),
+    }?;
+
+    let path = ["inputs", input, "url"];
+    find_path(&mut set.entries(), &path)
+}
+
+/// Finds the declaration of an attribute of an input of an input, e.g.
+/// `inputs.crane.inputs.nixpkgs.follows` for the input path `["crane", "nixpkgs"]`.
+fn find_nested_input_attr(
+    input_path: &[String],
+    attr: &str,
+    flake: &Root,
+) -> Result<AttrpathValue> {
+    let Some(ast::Expr::AttrSet(set)) = flake.expr() else {
+        return Err(Error::NixParserError(String::from("root isn't a set")));
+    };
+
+    let mut path = input_path
+        .iter()
+        .flat_map(|input| ["inputs", input.as_str()])
+        .collect::<Vec<_>>();
+    path.push(attr);
+    find_path(&mut set.entries(), &path)
+}
+
+/// The input paths declared by flake.nix in the order of their declarations, e.g. `["crane"]`
+/// and `["crane", "nixpkgs"]` for `inputs.crane.inputs.nixpkgs.follows = "nixpkgs";`.
+fn declared_input_paths(flake: &Root) -> Result<Vec<Vec<String>>> {
+    let Some(ast::Expr::AttrSet(set)) = flake.expr() else {
+        return Err(Error::NixParserError(String::from("root isn't a set")));
+    };
+
+    let mut attrpaths = Vec::new();
+    collect_attrpaths(&mut set.entries(), &[], &mut attrpaths);
+
+    let mut input_paths = Vec::<Vec<String>>::new();
+    for attrpath in attrpaths {
+        let mut input_path = Vec::new();
+        for pair in attrpath.chunks_exact(2) {
+            let [inputs, input] = pair else { break };
+            if inputs != "inputs" {
+                break;
+            }
+            input_path.push(input.clone());
+            if !input_paths.contains(&input_path) {
+                input_paths.push(input_path.clone());
+            }
+        }
+    }
+
+    Ok(input_paths)
+}
+
+/// The outputs function, `None` when outputs isn't declared as a function.
+fn find_outputs_lambda(flake: &Root) -> Result<Option<ast::Lambda>> {
+    let Some(ast::Expr::AttrSet(set)) = flake.expr() else {
+        return Err(Error::NixParserError(String::from("root isn't a set")));
+    };
+
+    match find_path(&mut set.entries(), &["outputs"])
+        .ok()
+        .and_then(|outputs| outputs.value())
+    {
+        Some(ast::Expr::Lambda(lambda)) => Ok(Some(lambda)),
+        _ => Ok(None),
+    }
+}
+
+/// The arguments of the outputs function, e.g. `self` and `nixpkgs` for
+/// `outputs = { self, nixpkgs }: ...`.
+fn outputs_args(flake: &Root) -> Result<Vec<String>> {
+    let args = match find_outputs_lambda(flake)?.and_then(|lambda| lambda.param()) {
+        Some(ast::Param::Pattern(pattern)) => pattern
+            .pat_entries()
+            .filter_map(|entry| entry.ident())
+            .filter_map(|ident| ident.ident_token())
+            .map(|token| token.text().to_string())
+            .collect(),
+        _ => vec![],
+    };
+
+    Ok(args)
+}
+
+/// Flattens the nested attribute sets into the attribute paths of their values, e.g.
+/// `inputs = { crane.url = ...; };` or `inputs."crane".url = ...;` into
+/// `["inputs", "crane", "url"]`.
+fn collect_attrpaths(
+    attrset: &mut AstChildren<Entry>,
+    prefix: &[String],
+    attrpaths: &mut Vec<Vec<String>>,
+) {
+    for entry in attrset {
+        let ast::Entry::AttrpathValue(attrpath_value) = entry else {
+            continue;
+        };
+        // attributes with interpolated names can't be resolved statically
+        let Some(attrs) = attrpath_value
+            .attrpath()
+            .into_iter()
+            .flat_map(|attrpath| attrpath.attrs())
+            .map(|attr| attr_text(&attr))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let mut attrpath = prefix.to_vec();
+        attrpath.extend(attrs);
+
+        match attrpath_value.value() {
+            Some(ast::Expr::AttrSet(set)) => {
+                collect_attrpaths(&mut set.entries(), &attrpath, attrpaths);
+            }
+            _ => attrpaths.push(attrpath),
+        }
+    }
+}
+
+fn find_path(attrset: &mut AstChildren<Entry>, path: &[&str]) -> Result<AttrpathValue> {
+    attrset
+        .find_map(|entry| match entry {
+            ast::Entry::AttrpathValue(attrpath_value) => {
+                let attrs = attrpath_value.attrpath().unwrap().attrs();
+                let idents = attrs
+                    .into_iter()
+                    .filter_map(|a| match a {
+                        Attr::Ident(ident) => Some(ident.ident_token().unwrap().text().to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                if idents.len() > path.len() {
+                    return None;
+                }
+                let to_compare = path[0..idents.len()].to_vec();
+                let remaining = path[idents.len()..path.len()].to_vec();
+
+                if idents == to_compare {
+                    if remaining.is_empty() {
+                        Some(Ok(attrpath_value))
+                    } else {
+                        match attrpath_value.value() {
+                            Some(ast::Expr::AttrSet(set)) => {
+                                Some(find_path(&mut set.entries(), &remaining))
+                            }
+                            _ => None,
+                        }
+                    }
+                } else {
+                    None
+                }
+            }
+            ast::Entry::Inherit(_) => None,
+        })
+        .unwrap_or_else(|| Err(Error::NixParserError(String::from("Couldn't find path"))))
+}
+
+pub(super) fn string_content(attr: &AttrpathValue) -> Option<String> {
+    match attr.value().unwrap() {
+        ast::Expr::Str(str) => str.normalized_parts().iter().find_map(|p| match p {
+            InterpolPart::Literal(l) => Some(l.clone()),
+            InterpolPart::Interpolation(_) => None,
+        }),
+        _ => None,
+    }
+}
+
+pub(super) fn override_input_url(input: &AttrpathValue, input_url: &str) -> GreenNode {
+    let mut builder = GreenNodeBuilder::new();
+    builder.start_node(rowan::SyntaxKind(rnix::SyntaxKind::NODE_STRING as u16));
+    builder.token(
+        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_START as u16),
+        "\"",
+    );
+    builder.token(
+        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_CONTENT as u16),
+        input_url,
+    );
+    builder.token(
+        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_END as u16),
+        "\"",
+    );
+    builder.finish_node();
+    let node = builder.finish();
+
+    match input.value() {
+        Some(ast::Expr::Str(str)) => str.syntax().replace_with(node),
+        _ => todo!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::FlakeNix;
+
+    #[test]
+    fn test_with_input_url_replaces_the_url_only() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs = {
+    nix-rust-utils.url = "github:voidcontext/nix-rust-utils/v0.3.0";
+  };
+  outputs = {...}: {};
+}"#,
+        ));
+
+        let updated = flake_nix
+            .with_input_url("nix-rust-utils", "github:voidcontext/nix-rust-utils/v0.4.0")
+            .unwrap();
+
+        assert_eq!(
+            flake_nix.input_url("nix-rust-utils").unwrap(),
+            "github:voidcontext/nix-rust-utils/v0.3.0"
+        );
+        assert_eq!(
+            updated.as_string(),
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs = {
+    nix-rust-utils.url = "github:voidcontext/nix-rust-utils/v0.4.0";
+  };
+  outputs = {...}: {};
+}"#
+        );
+    }
+
+    #[rstest]
+    #[case(
+        r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  outputs = {...}: {};
+}"#,
+        r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  outputs = {...}: {};
+}"#
+    )]
+    #[case(
+        r#"{
+  inputs = {
+    crane.url = "github:ipetkov/crane";
+  };
+}"#,
+        r#"{
+  inputs = {
+    crane.url = "github:ipetkov/crane";
+    crane.inputs.nixpkgs.follows = "nixpkgs";
+  };
+}"#
+    )]
+    #[case(
+        r#"{
+  inputs = {
+    crane = {
+      url = "github:ipetkov/crane";
+      inputs.flake-utils.follows = "flake-utils";
+    };
+  };
+}"#,
+        r#"{
+  inputs = {
+    crane = {
+      url = "github:ipetkov/crane";
+      inputs.nixpkgs.follows = "nixpkgs";
+      inputs.flake-utils.follows = "flake-utils";
+    };
+  };
+}"#
+    )]
+    fn test_with_follows_inserts_declaration_after_url(
+        #[case] flake_nix: &str,
+        #[case] expected: &str,
+    ) {
+        let updated = FlakeNix::new(String::from(flake_nix))
+            .with_follows(&[String::from("crane"), String::from("nixpkgs")], "nixpkgs")
+            .unwrap();
+
+        assert_eq!(updated.as_string(), expected);
+    }
+
+    #[test]
+    fn test_with_follows_nested_input() {
+        let updated = FlakeNix::new(String::from(
+            r#"{ inputs.crane.url = "github:ipetkov/crane"; }"#,
+        ))
+        .with_follows(
+            &[
+                String::from("crane"),
+                String::from("rust-overlay"),
+                String::from("nixpkgs.lib"),
+            ],
+            "nixpkgs",
+        )
+        .unwrap();
+
+        assert_eq!(
+            updated.as_string(),
+            r#"{ inputs.crane.url = "github:ipetkov/crane";
+inputs.crane.inputs.rust-overlay.inputs."nixpkgs.lib".follows = "nixpkgs"; }"#
+        );
+    }
+
+    #[test]
+    fn test_nested_input_attr() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  inputs = {
+    nixpkgs.url = "nixpkgs/release-23.05";
+    rust-overlay.inputs.flake-utils.url = "github:numtide/flake-utils";
+  };
+}"#,
+        ));
+        let path = |path: &[&str]| {
+            path.iter()
+                .map(|input| String::from(*input))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            flake_nix.input_url("nixpkgs").unwrap(),
+            "nixpkgs/release-23.05"
+        );
+        assert_eq!(
+            flake_nix.nested_input_attr(&path(&["crane", "nixpkgs"]), "follows"),
+            Some(String::from("nixpkgs"))
+        );
+        assert_eq!(
+            flake_nix.nested_input_attr(&path(&["rust-overlay", "flake-utils"]), "url"),
+            Some(String::from("github:numtide/flake-utils"))
+        );
+        assert_eq!(
+            flake_nix.nested_input_attr(&path(&["crane", "nixpkgs"]), "url"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_declared_inputs_and_outputs_inputs() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  inputs = {
+    nixpkgs.url = "nixpkgs/release-23.05";
+    rust-overlay.inputs.flake-utils.url = "github:numtide/flake-utils";
+  };
+  inputs."nixpkgs-github".url = "github:NixOS/nixpkgs";
+  inputs.${"dyn" + "amic"}.url = "github:NixOS/nixpkgs";
+  outputs = { self, nixpkgs, flake-utils, ... }: {};
+}"#,
+        ));
+
+        assert_eq!(
+            flake_nix.declared_inputs().unwrap(),
+            vec![
+                vec![String::from("crane")],
+                vec![String::from("crane"), String::from("nixpkgs")],
+                vec![String::from("nixpkgs")],
+                vec![String::from("rust-overlay")],
+                vec![String::from("rust-overlay"), String::from("flake-utils")],
+                vec![String::from("nixpkgs-github")],
+            ]
+        );
+        assert_eq!(
+            flake_nix.outputs_inputs().unwrap(),
+            vec![String::from("nixpkgs"), String::from("flake-utils")]
+        );
+    }
+
+    #[rstest]
+    #[case::taken_by_name("{ self, nixpkgs, crane, flake-utils }: { lib = nixpkgs.lib; }", &["crane", "flake-utils"])]
+    #[case::through_binding(
+        "{ self, ... }@inputs: { lib = inputs.nixpkgs.lib; inherit (inputs) crane; }",
+        &["flake-utils"]
+    )]
+    #[case::ident_param("inputs: { lib = inputs.nixpkgs.lib; }", &["crane", "flake-utils"])]
+    #[case::binding_passed_on("{ self, ... }@inputs: import ./outputs.nix inputs", &[])]
+    #[case::self_inputs("{ self, nixpkgs, ... }: { lib = self.inputs.crane.lib; }", &["flake-utils"])]
+    #[case::inherited("{ self, nixpkgs, crane, ... }: { inherit crane; }", &["flake-utils"])]
+    #[case::attr_names_are_not_references(
+        "{ self, nixpkgs, crane, ... }: { crane = nixpkgs.crane; }",
+        &["crane", "flake-utils"]
+    )]
+    fn test_unused_inputs(#[case] outputs: &str, #[case] expected: &[&str]) {
+        let flake_nix = FlakeNix::new(format!(
+            r#"{{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs.crane.inputs.nixpkgs.follows = "nixpkgs";
+  inputs.flake-utils.url = "github:numtide/flake-utils";
+  outputs = {outputs};
+}}"#
+        ));
+
+        assert_eq!(flake_nix.unused_inputs().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_without_input() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs = {
+    flake-utils.url = "github:numtide/flake-utils";
+    crane.inputs.nixpkgs.follows = "nixpkgs";
+  };
+  outputs = { self, nixpkgs, crane, flake-utils }: {};
+}"#,
+        ));
+
+        assert_eq!(
+            flake_nix.without_input("crane").unwrap().as_string(),
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs = {
+    flake-utils.url = "github:numtide/flake-utils";
+  };
+  outputs = { self, nixpkgs, flake-utils }: {};
+}"#
+        );
+        assert_eq!(
+            flake_nix.without_input("flake-utils").unwrap().as_string(),
+            r#"{
+  inputs.nixpkgs.url = "nixpkgs/release-23.05";
+  inputs.crane.url = "github:ipetkov/crane";
+  inputs = {
+    crane.inputs.nixpkgs.follows = "nixpkgs";
+  };
+  outputs = { self, nixpkgs, crane }: {};
+}"#
+        );
+        assert!(flake_nix.without_input("rust-overlay").is_err());
+    }
+
+    #[test]
+    fn test_without_input_on_one_line() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{ inputs.nixpkgs.url = "nixpkgs"; inputs.crane.url = "github:ipetkov/crane"; outputs = { self, nixpkgs, crane }: {}; }"#,
+        ));
+
+        assert_eq!(
+            flake_nix.without_input("crane").unwrap().as_string(),
+            r#"{ inputs.nixpkgs.url = "nixpkgs"; outputs = { self, nixpkgs }: {}; }"#
+        );
+    }
+
+    #[test]
+    fn test_without_input_only_taken_by_the_outputs() {
+        let flake_nix = FlakeNix::new(String::from(
+            r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  outputs = { self, nixpkgs, crane }: { lib = crane.lib; };
+}"#,
+        ));
+
+        assert_eq!(
+            flake_nix.without_input("nixpkgs").unwrap().as_string(),
+            r#"{
+  inputs.crane.url = "github:ipetkov/crane";
+  outputs = { self, crane }: { lib = crane.lib; };
+}"#
+        );
+        assert!(flake_nix.without_input("flake-utils").is_err());
+    }
 }
@@ -0,0 +1,427 @@
+//! Editing of flake references in their URL-like form, e.g. `github:owner/repo/ref`,
+//! `git+https://host/repo.git?ref=main` or `nixpkgs/release-23.05`.
+
+use crate::domain::{Error, Result};
+
+use super::{Original, OriginalRef, OriginalRev, OriginalSource};
+
+const FORGE_SCHEMES: [&str; 3] = ["github:", "gitlab:", "sourcehut:"];
+
+/// Replaces the ref of the flake reference with `new_ref`, dropping the pinned revision if there
+/// is one. Refs that can't be part of the path (e.g. `refs/tags/v0.4.0`) are set as the `ref`
+/// query parameter.
+pub(crate) fn with_ref(url: &str, new_ref: &str) -> Result<String> {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+    let mut params = query
+        .map(|query| {
+            query
+                .split('&')
+                .filter(|param| !param.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    params.retain(|param| !param.starts_with("rev="));
+
+    let path_ref_segment = if FORGE_SCHEMES.iter().any(|scheme| base.starts_with(scheme)) {
+        // github:owner/repo[/ref-or-rev]
+        Some(2)
+    } else if base.contains(':') {
+        // git+https://..., path:..., etc. only support the ref query parameter
+        None
+    } else {
+        // indirect: id[/ref-or-rev[/rev]]
+        Some(1)
+    };
+
+    let base = if let Some(index) = path_ref_segment {
+        let (scheme, path) = match base.split_once(':') {
+            Some((scheme, path)) => (Some(scheme), path),
+            None => (None, base),
+        };
+        let mut segments = path.split('/').take(index).collect::<Vec<_>>();
+        if segments.len() < index || segments.iter().any(|segment| segment.is_empty()) {
+            return Err(Error::Error(format!("Invalid flake reference: {url}")));
+        }
+
+        let has_ref_param = params.iter().any(|param| param.starts_with("ref="));
+        if !new_ref.contains('/') && !has_ref_param {
+            segments.push(new_ref);
+        } else {
+            set_ref_param(&mut params, new_ref);
+        }
+
+        let path = segments.join("/");
+        match scheme {
+            Some(scheme) => format!("{scheme}:{path}"),
+            None => path,
+        }
+    } else {
+        set_ref_param(&mut params, new_ref);
+        String::from(base)
+    };
+
+    if params.is_empty() {
+        Ok(base)
+    } else {
+        Ok(format!("{base}?{}", params.join("&")))
+    }
+}
+
+/// A flake living in a git repository, e.g. `github:owner/repo/ref` or
+/// `git+https://host/repo.git?ref=main&dir=nix`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct GitFlakeRef {
+    /// The url the repository can be cloned from
+    pub(crate) url: String,
+    pub(crate) r#ref: Option<String>,
+    pub(crate) rev: Option<String>,
+    /// The directory of the flake inside the repository
+    pub(crate) dir: Option<String>,
+}
+
+impl GitFlakeRef {
+    /// Parses the flake reference, `None` when it isn't a URL-like reference but a local path.
+    pub(crate) fn parse(flake_ref: &str) -> Result<Option<Self>> {
+        let (base, query) = match flake_ref.split_once('?') {
+            Some((base, query)) => (base, query),
+            None => (flake_ref, ""),
+        };
+        let param = |name: &str| query_param(query, name);
+        let (mut r#ref, mut rev, dir) = (param("ref"), param("rev"), param("dir"));
+
+        let url = if let Some(url) = base.strip_prefix("git+") {
+            String::from(url)
+        } else if let Some((scheme @ ("github" | "gitlab" | "sourcehut"), path)) =
+            base.split_once(':')
+        {
+            let segments = path.split('/').collect::<Vec<_>>();
+            let (owner, repo, ref_or_rev) = match segments[..] {
+                [owner, repo] => (owner, repo, None),
+                [owner, repo, ref_or_rev] => (owner, repo, Some(ref_or_rev)),
+                _ => {
+                    return Err(Error::Error(format!(
+                        "Invalid flake reference: {flake_ref}"
+                    )))
+                }
+            };
+            match ref_or_rev {
+                Some(ref_or_rev) if is_rev(ref_or_rev) => rev = Some(String::from(ref_or_rev)),
+                Some(ref_or_rev) => r#ref = Some(String::from(ref_or_rev)),
+                None => {}
+            }
+
+            match scheme {
+                "github" => format!("https://github.com/{owner}/{repo}.git"),
+                "gitlab" => format!("https://gitlab.com/{owner}/{repo}.git"),
+                _ => format!("https://git.sr.ht/{owner}/{repo}"),
+            }
+        } else if base.contains(':') {
+            return Err(Error::Error(format!(
+                "Unsupported flake reference: {flake_ref}, only git repositories are supported"
+            )));
+        } else {
+            return Ok(None);
+        };
+        // git would take them for options
+        if [Some(&url), r#ref.as_ref(), rev.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|arg| arg.starts_with('-'))
+        {
+            return Err(Error::Error(format!(
+                "Invalid flake reference: {flake_ref}"
+            )));
+        }
+
+        Ok(Some(Self {
+            url,
+            r#ref,
+            rev,
+            dir,
+        }))
+    }
+}
+
+/// The `original` entry nix locks the flake reference with, e.g. the indirect `nixpkgs` with
+/// the ref `release-23.05` for `nixpkgs/release-23.05`. `None` for the kinds of references the
+/// lock isn't modelled for, e.g. paths and tarballs.
+pub(crate) fn original(flake_ref: &str) -> Option<Original> {
+    let (base, query) = match flake_ref.split_once('?') {
+        Some((base, query)) => (base, query),
+        None => (flake_ref, ""),
+    };
+    let (mut r#ref, mut rev) = (query_param(query, "ref"), query_param(query, "rev"));
+
+    let (source, ref_or_rev) = if let Some(url) = base.strip_prefix("git+") {
+        (
+            OriginalSource::Git {
+                url: String::from(url),
+            },
+            None,
+        )
+    } else if let Some((scheme @ ("github" | "gitlab"), path)) = base.split_once(':') {
+        let (owner, repo, ref_or_rev) = match path.split('/').collect::<Vec<_>>()[..] {
+            [owner, repo] => (owner, repo, None),
+            [owner, repo, ref_or_rev] => (owner, repo, Some(ref_or_rev)),
+            _ => return None,
+        };
+        let (owner, repo) = (String::from(owner), String::from(repo));
+        let source = if scheme == "github" {
+            OriginalSource::GitHub { owner, repo }
+        } else {
+            OriginalSource::GitLab { owner, repo }
+        };
+        (source, ref_or_rev)
+    } else {
+        let indirect = base.strip_prefix("flake:").unwrap_or(base);
+        let is_id = |id: &str| {
+            id.starts_with(|c: char| c.is_ascii_alphabetic())
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        };
+        match indirect.split('/').collect::<Vec<_>>()[..] {
+            [id, ..] if !is_id(id) => return None,
+            [id] => (
+                OriginalSource::Indirect {
+                    id: String::from(id),
+                },
+                None,
+            ),
+            [id, ref_or_rev] => (
+                OriginalSource::Indirect {
+                    id: String::from(id),
+                },
+                Some(ref_or_rev),
+            ),
+            [id, ref_name, commit] if is_rev(commit) => {
+                r#ref = Some(String::from(ref_name));
+                rev = Some(String::from(commit));
+                (
+                    OriginalSource::Indirect {
+                        id: String::from(id),
+                    },
+                    None,
+                )
+            }
+            _ => return None,
+        }
+    };
+    match ref_or_rev {
+        Some(ref_or_rev) if is_rev(ref_or_rev) => rev = Some(String::from(ref_or_rev)),
+        Some(ref_or_rev) => r#ref = Some(String::from(ref_or_rev)),
+        None => {}
+    }
+
+    Some(Original {
+        rev: rev.map(OriginalRev::new),
+        r#ref: r#ref.map(OriginalRef::new),
+        source,
+    })
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .find_map(|param| param.strip_prefix(name)?.strip_prefix('='))
+        .map(String::from)
+}
+
+/// Whether `s` is a full git commit hash.
+pub(crate) fn is_rev(s: &str) -> bool {
+    s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn set_ref_param(params: &mut Vec<String>, new_ref: &str) {
+    let param = format!("ref={new_ref}");
+    match params.iter_mut().find(|param| param.starts_with("ref=")) {
+        Some(existing) => *existing = param,
+        None => params.insert(0, param),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use crate::domain::nix::OriginalSource;
+
+    use super::{original, with_ref, GitFlakeRef};
+
+    #[rstest]
+    #[case(
+        "github:voidcontext/nix-rust-utils/v0.3.0",
+        "v0.4.0",
+        "github:voidcontext/nix-rust-utils/v0.4.0"
+    )]
+    #[case(
+        "github:voidcontext/nix-rust-utils",
+        "v0.4.0",
+        "github:voidcontext/nix-rust-utils/v0.4.0"
+    )]
+    #[case(
+        "github:NixOS/nixpkgs/dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "release-23.11",
+        "github:NixOS/nixpkgs/release-23.11"
+    )]
+    #[case(
+        "github:voidcontext/nix-rust-utils?ref=refs/tags/v0.3.0",
+        "refs/tags/v0.4.0",
+        "github:voidcontext/nix-rust-utils?ref=refs/tags/v0.4.0"
+    )]
+    #[case(
+        "github:voidcontext/nix-rust-utils/v0.3.0",
+        "refs/tags/v0.4.0",
+        "github:voidcontext/nix-rust-utils?ref=refs/tags/v0.4.0"
+    )]
+    #[case(
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git?ref=refs/tags/v0.3.0&rev=3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+        "refs/tags/v0.4.0",
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git?ref=refs/tags/v0.4.0"
+    )]
+    #[case(
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git?shallow=1",
+        "main",
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git?ref=main&shallow=1"
+    )]
+    #[case("nixpkgs/release-23.05", "release-23.11", "nixpkgs/release-23.11")]
+    #[case("nixpkgs", "release-23.11", "nixpkgs/release-23.11")]
+    fn test_with_ref(#[case] url: &str, #[case] new_ref: &str, #[case] expected: &str) {
+        assert_eq!(with_ref(url, new_ref).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "github:org/platform",
+        "https://github.com/org/platform.git",
+        None,
+        None,
+        None
+    )]
+    #[case(
+        "github:org/platform/release-23.11?dir=nix",
+        "https://github.com/org/platform.git",
+        Some("release-23.11"),
+        None,
+        Some("nix")
+    )]
+    #[case(
+        "gitlab:org/platform/dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "https://gitlab.com/org/platform.git",
+        None,
+        Some("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad"),
+        None
+    )]
+    #[case(
+        "git+https://example.com/platform.git?ref=main",
+        "https://example.com/platform.git",
+        Some("main"),
+        None,
+        None
+    )]
+    #[case(
+        "git+file:///src/platform?rev=dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "file:///src/platform",
+        None,
+        Some("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad"),
+        None
+    )]
+    fn test_git_flake_ref_parse(
+        #[case] flake_ref: &str,
+        #[case] url: &str,
+        #[case] r#ref: Option<&str>,
+        #[case] rev: Option<&str>,
+        #[case] dir: Option<&str>,
+    ) {
+        assert_eq!(
+            GitFlakeRef::parse(flake_ref).unwrap(),
+            Some(GitFlakeRef {
+                url: String::from(url),
+                r#ref: r#ref.map(String::from),
+                rev: rev.map(String::from),
+                dir: dir.map(String::from),
+            })
+        );
+    }
+
+    #[test]
+    fn test_git_flake_ref_parse_rejects_non_git_references() {
+        assert_eq!(GitFlakeRef::parse("../platform").unwrap(), None);
+        assert!(GitFlakeRef::parse("https://example.com/platform.tar.gz").is_err());
+        assert!(GitFlakeRef::parse("github:org").is_err());
+    }
+
+    #[rstest]
+    #[case("git+file:///src/platform?ref=--upload-pack=/tmp/evil.sh")]
+    #[case("git+https://example.com/platform.git?rev=-c")]
+    #[case("git+--upload-pack=/tmp/evil.sh")]
+    #[case("github:org/platform/--upload-pack=/tmp/evil.sh")]
+    fn test_git_flake_ref_parse_rejects_options(#[case] flake_ref: &str) {
+        assert!(GitFlakeRef::parse(flake_ref).is_err());
+    }
+
+    #[rstest]
+    #[case("nixpkgs", "nixpkgs", None, None)]
+    #[case("nixpkgs/release-23.05", "nixpkgs", Some("release-23.05"), None)]
+    #[case(
+        "flake:nixpkgs/nixos-23.05/dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "nixpkgs",
+        Some("nixos-23.05"),
+        Some("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad")
+    )]
+    #[case(
+        "github:ipetkov/crane/v0.16.0",
+        "github:ipetkov/crane",
+        Some("v0.16.0"),
+        None
+    )]
+    #[case(
+        "gitlab:org/platform?rev=dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad",
+        "gitlab:org/platform",
+        None,
+        Some("dc1517e4b9e481e15bf7c80740a6a8a1423fe3ad")
+    )]
+    #[case(
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git?ref=refs/tags/v0.3.0&shallow=1",
+        "git+https://git.vdx.hu/voidcontext/nix-rust-utils.git",
+        Some("refs/tags/v0.3.0"),
+        None
+    )]
+    fn test_original(
+        #[case] flake_ref: &str,
+        #[case] source: &str,
+        #[case] r#ref: Option<&str>,
+        #[case] rev: Option<&str>,
+    ) {
+        let original = original(flake_ref).unwrap();
+
+        assert_eq!(original.source.to_string(), source);
+        assert_eq!(original.r#ref.as_deref(), r#ref);
+        assert_eq!(original.rev.as_deref(), rev);
+    }
+
+    #[test]
+    fn test_original_of_unmodelled_references() {
+        assert_eq!(
+            original("path:../platform").map(|original| original.source),
+            None
+        );
+        assert_eq!(
+            original("../platform").map(|original| original.source),
+            None
+        );
+        assert_eq!(
+            original("https://example.com/platform.tar.gz").map(|original| original.source),
+            None
+        );
+        assert!(matches!(
+            original("github:org/platform").map(|original| original.source),
+            Some(OriginalSource::GitHub { .. })
+        ));
+    }
+}
@@ -1,15 +1,18 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::domain::Result;
 
 mod flake_lock;
 mod flake_nix;
+pub(crate) mod flake_url;
 mod sync_service;
 mod sync_strategy;
 
+#[cfg(test)]
+pub(crate) use flake_lock::fixtures;
 pub(crate) use flake_lock::{
-    FlakeLock, InputReference, Locked, LockedRef, LockedRev, LockedSource, Node, Original,
-    OriginalRef, OriginalRev, OriginalSource, RootNode,
+    FlakeLock, InputReference, LockEdgeTarget, Locked, LockedRef, LockedRev, LockedSource, Node,
+    Original, OriginalRef, OriginalRev, OriginalSource, RootNode,
 };
 #[allow(clippy::module_name_repetitions)]
 pub(crate) use flake_nix::FlakeNix;
@@ -21,7 +24,18 @@ pub trait Flake {
     fn load_lock_from<P: AsRef<Path>>(&self, p: P) -> Result<FlakeLock>;
     fn load_from<P: AsRef<Path>>(&self, p: P) -> Result<FlakeNix>;
 
+    /// Finds every flake, i.e. every directory containing both a `flake.nix` and a `flake.lock`,
+    /// under the given directory tree.
+    fn discover<P: AsRef<Path>>(&self, root: P) -> Result<Vec<PathBuf>>;
+
     fn write<P: AsRef<Path>>(&self, p: P, flake: &FlakeNix) -> Result<()>;
 
     fn override_input<P: AsRef<Path>>(&self, p: P, input: &str, url: &str) -> Result<()>;
+
+    /// Updates flake.lock to match flake.nix, e.g. after inputs were added or changed.
+    fn lock<P: AsRef<Path>>(&self, p: P) -> Result<()>;
+
+    /// Removes the given nodes from flake.lock, leaving the rest of it untouched, including what
+    /// [`FlakeLock`] doesn't model.
+    fn remove_lock_nodes<P: AsRef<Path>>(&self, p: P, nodes: &[String]) -> Result<()>;
 }
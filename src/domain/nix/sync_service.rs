@@ -1,17 +1,9 @@
-use rnix::{
-    ast::{self, Attr, AttrpathValue, Entry, HasEntry, InterpolPart},
-    Root,
-};
-use rowan::{
-    ast::{AstChildren, AstNode},
-    GreenNode, GreenNodeBuilder,
-};
-
 use crate::domain::{self, commands::SyncInputNames};
 use crate::domain::{Error, Result};
 
 use super::{
     flake_lock::{Locked, LockedSource, Original, OriginalSource},
+    flake_nix::{find_input_url, override_input_url, string_content},
     FlakeLock, FlakeNix, SyncStrategy,
 };
 
@@ -74,7 +66,7 @@ impl SyncService for SyncServiceImpl {
 
         let override_url = override_url(&src_original, &src_locked)?;
 
-        log::info!("src: {src_original:?} == dst: {dst_original:?}");
+        log::debug!("src: {src_original:?} == dst: {dst_original:?}");
 
         if src_original.source == dst_original.source {
             if src_original.r#ref == dst_original.r#ref && src_original.rev == dst_original.rev {
@@ -114,93 +106,6 @@ impl SyncService for SyncServiceImpl {
         Ok(FlakeNix::new(updated_flake.to_string()))
     }
 }
-fn find_input_url(input: &str, flake: &Root) -> Result<AttrpathValue> {
-    let expr = flake.expr().unwrap();
-    let set = match expr {
-        ast::Expr::AttrSet(set) => Ok(set),
-        _ => Err(domain::Error::NixParserError(String::from(
-            "root isn't a set",
-        ))),
-    }?;
-
-    let path = ["inputs", input, "url"];
-    find_path(&mut set.entries(), &path)
-}
-
-fn find_path(attrset: &mut AstChildren<Entry>, path: &[&str]) -> Result<AttrpathValue> {
-    attrset
-        .find_map(|entry| match entry {
-            ast::Entry::AttrpathValue(attrpath_value) => {
-                let attrs = attrpath_value.attrpath().unwrap().attrs();
-                let idents = attrs
-                    .into_iter()
-                    .filter_map(|a| match a {
-                        Attr::Ident(ident) => Some(ident.ident_token().unwrap().text().to_string()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>();
-
-                let to_compare = path[0..idents.len()].to_vec();
-                let remaining = path[idents.len()..path.len()].to_vec();
-
-                if idents == to_compare {
-                    if remaining.is_empty() {
-                        Some(Ok(attrpath_value))
-                    } else {
-                        match attrpath_value.value() {
-                            Some(ast::Expr::AttrSet(set)) => {
-                                Some(find_path(&mut set.entries(), &remaining))
-                            }
-                            _ => None,
-                        }
-                    }
-                } else {
-                    None
-                }
-            }
-            ast::Entry::Inherit(_) => None,
-        })
-        .unwrap_or_else(|| {
-            Err(domain::Error::NixParserError(String::from(
-                "Couldn't find path",
-            )))
-        })
-}
-
-fn string_content(attr: &AttrpathValue) -> Option<String> {
-    match attr.value().unwrap() {
-        rnix::ast::Expr::Str(str) => str.normalized_parts().iter().find_map(|p| match p {
-            InterpolPart::Literal(l) => Some(l.clone()),
-            InterpolPart::Interpolation(_) => None,
-        }),
-        _ => None,
-    }
-}
-
-fn override_input_url(input: &AttrpathValue, input_url: &str) -> GreenNode {
-    let mut builder = GreenNodeBuilder::new();
-    builder.start_node(rowan::SyntaxKind(rnix::SyntaxKind::NODE_STRING as u16));
-    builder.token(
-        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_START as u16),
-        "\"",
-    );
-    builder.token(
-        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_CONTENT as u16),
-        input_url,
-    );
-    builder.token(
-        rowan::SyntaxKind(rnix::SyntaxKind::TOKEN_STRING_END as u16),
-        "\"",
-    );
-    builder.finish_node();
-    let node = builder.finish();
-
-    match input.value() {
-        Some(rnix::ast::Expr::Str(str)) => str.syntax().replace_with(node),
-        _ => todo!(),
-    }
-}
-
 fn override_url(original: &Original, locked: &Locked) -> Result<String> {
     match &original.source {
         OriginalSource::Indirect { id } => Ok(format!("{id}/{}", &*locked.rev)),
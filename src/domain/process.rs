@@ -0,0 +1,9 @@
+use std::path::Path;
+
+use crate::domain::Result;
+
+pub trait Process {
+    /// Runs `program` with the given arguments in `dir`, or in the current directory, and returns
+    /// its standard output. Fails when the program exits with a non-zero status.
+    fn run(&self, program: &str, args: &[&str], dir: Option<&Path>) -> Result<String>;
+}
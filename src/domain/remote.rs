@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use crate::domain::Result;
+
+/// The refs of a remote repository and the commits they point to, annotated tags are resolved to
+/// the tagged commit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteRefs {
+    head: Option<String>,
+    refs: BTreeMap<String, String>,
+}
+
+impl RemoteRefs {
+    #[must_use]
+    pub fn new(head: Option<String>, refs: BTreeMap<String, String>) -> Self {
+        Self { head, refs }
+    }
+
+    /// The commit of the default branch.
+    #[must_use]
+    pub fn head(&self) -> Option<&str> {
+        self.head.as_deref()
+    }
+
+    /// The commit of a full ref name (e.g. `refs/tags/v0.3.0`), or of a branch or tag name.
+    #[must_use]
+    pub fn resolve(&self, r#ref: &str) -> Option<&str> {
+        [
+            String::from(r#ref),
+            format!("refs/heads/{ref}"),
+            format!("refs/tags/{ref}"),
+        ]
+        .iter()
+        .find_map(|name| self.refs.get(name))
+        .map(String::as_str)
+    }
+
+    /// The names of the tags, without the `refs/tags/` prefix.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.refs
+            .keys()
+            .filter_map(|name| name.strip_prefix("refs/tags/"))
+    }
+
+    /// The names of the branches, without the `refs/heads/` prefix.
+    pub fn branches(&self) -> impl Iterator<Item = &str> {
+        self.refs
+            .keys()
+            .filter_map(|name| name.strip_prefix("refs/heads/"))
+    }
+}
+
+pub trait Remote {
+    /// Lists the refs of the repository at `url`, without cloning it.
+    fn refs(&self, url: &str) -> Result<RemoteRefs>;
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::collections::HashMap;
+
+    use crate::domain::{Error, Result};
+
+    use super::{Remote, RemoteRefs};
+
+    /// A [`Remote`] that knows the refs of the given urls.
+    #[derive(Default)]
+    pub(crate) struct TestRemote {
+        pub(crate) repositories: HashMap<String, RemoteRefs>,
+    }
+
+    impl Remote for TestRemote {
+        fn refs(&self, url: &str) -> Result<RemoteRefs> {
+            self.repositories
+                .get(url)
+                .cloned()
+                .ok_or_else(|| Error::ProcessError(format!("repository not found: {url}")))
+        }
+    }
+}
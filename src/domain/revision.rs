@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::domain::{
+    nix::{LockedRev, LockedSource},
+    Result,
+};
+
+/// Metadata of the commit an input is locked to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    #[serde(with = "time::serde::rfc3339")]
+    pub commit_date: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub author_date: OffsetDateTime,
+    pub author: Option<String>,
+    /// The first line of the commit message
+    pub subject: Option<String>,
+    /// The refs the commit is reachable from, empty when the provider doesn't know them.
+    #[serde(default)]
+    pub refs: Vec<String>,
+}
+
+pub trait RevisionProvider {
+    /// Looks up the given revision of the source, `None` when the revision is unknown to this
+    /// provider.
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> Result<Option<Revision>>;
+}
+
+/// Asks the first provider, then the second one when the first doesn't know the revision. Fails
+/// only when neither of them finds the revision and at least one of them failed.
+impl<A: RevisionProvider, B: RevisionProvider> RevisionProvider for (A, B) {
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> Result<Option<Revision>> {
+        let first = self.0.revision(source, rev);
+        if let Ok(Some(revision)) = first {
+            return Ok(Some(revision));
+        }
+
+        match (first, self.1.revision(source, rev)) {
+            (_, Ok(Some(revision))) => Ok(Some(revision)),
+            (Err(err), _) | (_, Err(err)) => Err(err),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::collections::HashMap;
+
+    use crate::domain::{
+        nix::{LockedRev, LockedSource},
+        Error, Result,
+    };
+
+    use super::{Revision, RevisionProvider};
+
+    /// A [`RevisionProvider`] that knows the given revisions of any source, or fails as if it
+    /// was offline.
+    #[derive(Default)]
+    pub(crate) struct TestRevisionProvider {
+        pub(crate) revisions: HashMap<String, Revision>,
+        pub(crate) offline: bool,
+    }
+
+    impl RevisionProvider for TestRevisionProvider {
+        fn revision(&self, _source: &LockedSource, rev: &LockedRev) -> Result<Option<Revision>> {
+            if self.offline {
+                return Err(Error::HttpError(String::from("offline")));
+            }
+
+            Ok(self.revisions.get(&**rev).cloned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+    use time::OffsetDateTime;
+
+    use crate::domain::nix::{LockedRev, LockedSource};
+
+    use super::{fixtures::TestRevisionProvider, Revision, RevisionProvider};
+
+    const REV: &str = "3892194d7b3293de8b30f1d19e2af45ba41ba8fd";
+
+    fn provider(offline: bool, known: bool) -> TestRevisionProvider {
+        let mut revisions = HashMap::new();
+        if known {
+            revisions.insert(String::from(REV), revision());
+        }
+        TestRevisionProvider { revisions, offline }
+    }
+
+    fn revision() -> Revision {
+        Revision {
+            commit_date: OffsetDateTime::UNIX_EPOCH,
+            author_date: OffsetDateTime::UNIX_EPOCH,
+            author: None,
+            subject: None,
+            refs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_chained_providers_fall_back_to_the_second_provider() {
+        let source = LockedSource::Git {
+            url: String::from("https://example.com/repo.git"),
+        };
+        let rev = LockedRev::from(REV);
+
+        assert_eq!(
+            (provider(false, true), provider(true, false))
+                .revision(&source, &rev)
+                .ok(),
+            Some(Some(revision()))
+        );
+        assert_eq!(
+            (provider(true, false), provider(false, true))
+                .revision(&source, &rev)
+                .ok(),
+            Some(Some(revision()))
+        );
+        assert_eq!(
+            (provider(false, false), provider(false, false))
+                .revision(&source, &rev)
+                .ok(),
+            Some(None)
+        );
+        assert!((provider(false, false), provider(true, false))
+            .revision(&source, &rev)
+            .is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use std::{collections::HashMap, env, str::FromStr};
+
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    domain::{
+        self,
+        http::HttpClient,
+        nix::{LockedRev, LockedSource},
+        process::Process,
+        revision::{Revision, RevisionProvider},
+    },
+    nix::config::{access_tokens, parse_access_tokens},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitLab,
+    /// Gitea and Forgejo share the same API
+    Gitea,
+}
+
+/// A forge hosting inputs, configured as `HOST=KIND[:API_URL]`, e.g. `git.vdx.hu=forgejo` or
+/// `git.example.com=gitlab:https://git.example.com/api/v4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forge {
+    pub host: String,
+    pub kind: ForgeKind,
+    pub api_url: String,
+}
+
+impl Forge {
+    #[must_use]
+    pub fn gitlab_com() -> Self {
+        Self {
+            host: String::from("gitlab.com"),
+            kind: ForgeKind::GitLab,
+            api_url: String::from("https://gitlab.com/api/v4"),
+        }
+    }
+}
+
+impl FromStr for Forge {
+    type Err = domain::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            domain::Error::Error(format!(
+                "Invalid forge '{s}', expected HOST=KIND[:API_URL], where KIND is gitlab, gitea or forgejo"
+            ))
+        };
+
+        let (host, rest) = s.split_once('=').ok_or_else(invalid)?;
+        let (kind, api_url) = match rest.split_once(':') {
+            Some((kind, api_url)) => (kind, Some(api_url)),
+            None => (rest, None),
+        };
+        let kind = match kind {
+            "gitlab" => ForgeKind::GitLab,
+            "gitea" | "forgejo" => ForgeKind::Gitea,
+            _ => return Err(invalid()),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        let api_url = api_url.map_or_else(
+            || match kind {
+                ForgeKind::GitLab => format!("https://{host}/api/v4"),
+                ForgeKind::Gitea => format!("https://{host}/api/v1"),
+            },
+            |api_url| String::from(api_url.trim_end_matches('/')),
+        );
+
+        Ok(Self {
+            host: String::from(host),
+            kind,
+            api_url,
+        })
+    }
+}
+
+/// Looks up revisions of GitLab inputs, and of git inputs hosted on a configured forge, using the
+/// forges' commit APIs.
+#[allow(clippy::module_name_repetitions)]
+pub struct ForgeRevisionProvider<H: HttpClient> {
+    http: H,
+    forges: Vec<Forge>,
+    tokens: HashMap<String, String>,
+}
+
+impl<H: HttpClient> ForgeRevisionProvider<H> {
+    /// `tokens` maps hosts to tokens, in the format of Nix's `access-tokens` setting, GitLab
+    /// tokens can be prefixed with `PAT:` or `OAuth2:`.
+    pub fn new(http: H, forges: Vec<Forge>, tokens: HashMap<String, String>) -> Self {
+        Self {
+            http,
+            forges,
+            tokens,
+        }
+    }
+
+    fn forge(&self, host: &str) -> Option<Forge> {
+        self.forges
+            .iter()
+            .find(|forge| forge.host == host)
+            .cloned()
+            .or_else(|| (host == "gitlab.com").then(Forge::gitlab_com))
+    }
+
+    fn headers(&self, forge: &Forge) -> Vec<(&'static str, String)> {
+        let Some(token) = self.tokens.get(&forge.host) else {
+            return vec![];
+        };
+
+        match forge.kind {
+            ForgeKind::GitLab => match token.split_once(':') {
+                Some(("OAuth2", token)) => vec![("Authorization", format!("Bearer {token}"))],
+                Some(("PAT", token)) => vec![("PRIVATE-TOKEN", String::from(token))],
+                _ => vec![("PRIVATE-TOKEN", token.clone())],
+            },
+            ForgeKind::Gitea => vec![("Authorization", format!("token {token}"))],
+        }
+    }
+
+    fn commit(
+        &self,
+        forge: &Forge,
+        project: &str,
+        rev: &LockedRev,
+    ) -> domain::Result<Option<Revision>> {
+        let url = match forge.kind {
+            ForgeKind::GitLab => format!(
+                "{}/projects/{}/repository/commits/{}",
+                forge.api_url,
+                project.replace('/', "%2F"),
+                &**rev
+            ),
+            ForgeKind::Gitea => {
+                format!("{}/repos/{project}/git/commits/{}", forge.api_url, &**rev)
+            }
+        };
+        let headers = self.headers(forge);
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect::<Vec<_>>();
+
+        let response = self.http.get(&url, &headers)?;
+        let parse_error =
+            |err: serde_json::Error| domain::Error::HttpError(format!("{url}: {err}"));
+
+        match response.status {
+            200 => match forge.kind {
+                ForgeKind::GitLab => {
+                    let commit: GitLabCommit =
+                        serde_json::from_str(&response.body).map_err(parse_error)?;
+                    Ok(Some(Revision {
+                        commit_date: commit.committed_date,
+                        author_date: commit.authored_date,
+                        author: commit.author_name,
+                        subject: commit.title,
+                        refs: vec![],
+                    }))
+                }
+                ForgeKind::Gitea => {
+                    let GiteaCommitResponse { commit } =
+                        serde_json::from_str(&response.body).map_err(parse_error)?;
+                    Ok(Some(Revision {
+                        commit_date: commit.committer.date,
+                        author_date: commit.author.date,
+                        author: commit.author.name,
+                        subject: commit.message.lines().next().map(String::from),
+                        refs: vec![],
+                    }))
+                }
+            },
+            404 | 422 => Ok(None),
+            status => Err(domain::Error::HttpError(format!(
+                "{url} responded with {status}"
+            ))),
+        }
+    }
+}
+
+/// Tokens of the forges: `LAMINA_FORGE_TOKENS` (in the format of `access-tokens`), then Nix's
+/// `access-tokens` setting.
+pub fn forge_tokens<P: Process>(process: &P) -> HashMap<String, String> {
+    let mut tokens = access_tokens(process);
+    if let Ok(env_tokens) = env::var("LAMINA_FORGE_TOKENS") {
+        tokens.extend(parse_access_tokens(&env_tokens));
+    }
+    tokens
+}
+
+#[derive(Deserialize)]
+struct GitLabCommit {
+    #[serde(with = "time::serde::rfc3339")]
+    committed_date: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    authored_date: OffsetDateTime,
+    author_name: Option<String>,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GiteaCommitResponse {
+    commit: GiteaCommit,
+}
+
+#[derive(Deserialize)]
+struct GiteaCommit {
+    author: GiteaSignature,
+    committer: GiteaSignature,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaSignature {
+    name: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    date: OffsetDateTime,
+}
+
+/// Splits a git url (`https://host/owner/repo.git`, `ssh://git@host:22/owner/repo`,
+/// `git@host:owner/repo.git`) into its host and repository path.
+fn host_and_project(url: &str) -> Option<(&str, &str)> {
+    let (rest, scp_like) = match url.split_once("://") {
+        Some((_, rest)) => (rest, false),
+        None => (url, true),
+    };
+    let rest = rest.split(['?', '#']).next()?;
+    let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+
+    let (host, path) = if scp_like {
+        rest.split_once(':')?
+    } else {
+        let (host, path) = rest.split_once('/')?;
+        (host.split(':').next()?, path)
+    };
+
+    let project = path.trim_matches('/').trim_end_matches(".git");
+
+    (!host.is_empty() && project.contains('/')).then_some((host, project))
+}
+
+impl<H: HttpClient> RevisionProvider for ForgeRevisionProvider<H> {
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> domain::Result<Option<Revision>> {
+        match source {
+            LockedSource::GitLab { owner, repo } => {
+                let forge = self.forge("gitlab.com").unwrap_or_else(Forge::gitlab_com);
+                self.commit(&forge, &format!("{owner}/{repo}"), rev)
+            }
+            LockedSource::Git { url } => match host_and_project(url)
+                .and_then(|(host, project)| self.forge(host).map(|forge| (forge, project)))
+            {
+                Some((forge, project)) => self.commit(&forge, project, rev),
+                None => Ok(None),
+            },
+            LockedSource::GitHub { .. } => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, thread};
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use time::OffsetDateTime;
+    use tiny_http::{Response, Server};
+
+    use crate::{
+        domain::{
+            nix::{LockedRev, LockedSource},
+            revision::{Revision, RevisionProvider},
+        },
+        http::UreqHttpClient,
+    };
+
+    use super::{host_and_project, Forge, ForgeKind, ForgeRevisionProvider};
+
+    const REV: &str = "3892194d7b3293de8b30f1d19e2af45ba41ba8fd";
+
+    /// Serves one request with the given body when the path matches, 404 otherwise, and returns
+    /// the path and headers of the request.
+    fn mock_server(
+        path: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<(String, Vec<String>)>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let url = String::from(request.url());
+            let headers = request.headers().iter().map(ToString::to_string).collect();
+
+            let response = if url == path {
+                Response::from_string(body)
+            } else {
+                Response::from_string("{}").with_status_code(404)
+            };
+            request.respond(response).unwrap();
+
+            (url, headers)
+        });
+
+        (url, handle)
+    }
+
+    #[allow(clippy::unreadable_literal)]
+    fn expected_revision() -> Revision {
+        Revision {
+            commit_date: OffsetDateTime::from_unix_timestamp(1685570000).unwrap(),
+            author_date: OffsetDateTime::from_unix_timestamp(1685000000).unwrap(),
+            author: Some(String::from("a")),
+            subject: Some(String::from("Release v0.3.0")),
+            refs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_forge_revision_provider_queries_gitlab() {
+        let (url, server) = mock_server(
+            "/api/v4/projects/voidcontext%2Fnix-rust-utils/repository/commits/3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+            r#"{"id": "3892194d", "committed_date": "2023-05-31T21:53:20Z", "authored_date": "2023-05-25T07:33:20Z", "author_name": "a", "title": "Release v0.3.0"}"#,
+        );
+        let provider = ForgeRevisionProvider::new(
+            UreqHttpClient::default(),
+            vec![format!("gitlab.com=gitlab:{url}/api/v4").parse().unwrap()],
+            HashMap::from([(String::from("gitlab.com"), String::from("PAT:glpat-secret"))]),
+        );
+
+        let revision = provider
+            .revision(
+                &LockedSource::GitLab {
+                    owner: String::from("voidcontext"),
+                    repo: String::from("nix-rust-utils"),
+                },
+                &LockedRev::from(REV),
+            )
+            .unwrap();
+
+        assert_eq!(revision, Some(expected_revision()));
+        let (_, headers) = server.join().unwrap();
+        assert!(headers.contains(&String::from("PRIVATE-TOKEN: glpat-secret")));
+    }
+
+    #[test]
+    fn test_forge_revision_provider_queries_forgejo() {
+        let (url, server) = mock_server(
+            "/api/v1/repos/voidcontext/nix-rust-utils/git/commits/3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+            r#"{"sha": "3892194d", "commit": {"author": {"name": "a", "date": "2023-05-25T07:33:20Z"}, "committer": {"name": "c", "date": "2023-05-31T21:53:20Z"}, "message": "Release v0.3.0\n\nChangelog"}}"#,
+        );
+        let provider = ForgeRevisionProvider::new(
+            UreqHttpClient::default(),
+            vec![format!("git.vdx.hu=forgejo:{url}/api/v1").parse().unwrap()],
+            HashMap::from([(String::from("git.vdx.hu"), String::from("secret"))]),
+        );
+
+        let revision = provider
+            .revision(
+                &LockedSource::Git {
+                    url: String::from("https://git.vdx.hu/voidcontext/nix-rust-utils.git"),
+                },
+                &LockedRev::from(REV),
+            )
+            .unwrap();
+
+        assert_eq!(revision, Some(expected_revision()));
+        let (_, headers) = server.join().unwrap();
+        assert!(headers.contains(&String::from("Authorization: token secret")));
+    }
+
+    #[test]
+    fn test_forge_revision_provider_skips_unknown_hosts() {
+        let provider =
+            ForgeRevisionProvider::new(UreqHttpClient::default(), vec![], HashMap::new());
+
+        let revision = provider
+            .revision(
+                &LockedSource::Git {
+                    url: String::from("https://git.vdx.hu/voidcontext/nix-rust-utils.git"),
+                },
+                &LockedRev::from(REV),
+            )
+            .unwrap();
+
+        assert_eq!(revision, None);
+    }
+
+    #[rstest]
+    #[case("git.vdx.hu=forgejo", ForgeKind::Gitea, "https://git.vdx.hu/api/v1")]
+    #[case(
+        "git.example.com=gitlab:http://localhost:8080/api/v4/",
+        ForgeKind::GitLab,
+        "http://localhost:8080/api/v4"
+    )]
+    fn test_parse_forge(#[case] forge: &str, #[case] kind: ForgeKind, #[case] api_url: &str) {
+        let forge = forge.parse::<Forge>().unwrap();
+
+        assert_eq!(forge.kind, kind);
+        assert_eq!(forge.api_url, api_url);
+    }
+
+    #[rstest]
+    #[case("https://git.vdx.hu/voidcontext/nix-rust-utils.git", Some(("git.vdx.hu", "voidcontext/nix-rust-utils")))]
+    #[case("ssh://git@git.vdx.hu:2222/voidcontext/nix-rust-utils?ref=main", Some(("git.vdx.hu", "voidcontext/nix-rust-utils")))]
+    #[case("git@gitlab.com:group/sub/repo.git", Some(("gitlab.com", "group/sub/repo")))]
+    #[case("file:///home/user/repo", None)]
+    fn test_host_and_project(#[case] url: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(host_and_project(url), expected);
+    }
+}
@@ -3,8 +3,12 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use ignore::{DirEntry, WalkBuilder};
+
 use crate::domain::{self, fs::FileSystem};
 
+const SKIPPED_DIRS: [&str; 2] = [".git", ".direnv"];
+
 pub fn ensure_file(path: &Path, file_name: &str) -> domain::Result<PathBuf> {
     let mut path = path.to_path_buf();
     if path.is_dir() {
@@ -27,6 +31,35 @@ pub fn ensure_file(path: &Path, file_name: &str) -> domain::Result<PathBuf> {
     }
 }
 
+/// Escapes every character of `s` that might not be valid in a file name, so different strings
+/// never get the same file name: bytes other than ASCII letters, digits and `-` are written as
+/// `_` followed by their hex code, e.g. `a_b/c` as `a_5fb_2fc`.
+#[must_use]
+pub fn file_name_of(s: &str) -> String {
+    s.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b == b'-' {
+                char::from(b).to_string()
+            } else {
+                format!("_{b:02x}")
+            }
+        })
+        .collect()
+}
+
+/// Lamina's cache directory: `$XDG_CACHE_HOME/lamina`, or `~/.cache/lamina` when
+/// `XDG_CACHE_HOME` isn't set
+pub fn cache_dir() -> domain::Result<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("lamina"))
+        .ok_or(domain::Error::Error(String::from(
+            "Couldn't determine the cache directory, neither XDG_CACHE_HOME nor HOME is set",
+        )))
+}
+
 pub struct OsFileSystem {}
 
 impl FileSystem for OsFileSystem {
@@ -47,4 +80,40 @@ impl FileSystem for OsFileSystem {
 
         Ok(())
     }
+
+    fn find_files<P: AsRef<Path>>(
+        &self,
+        root: P,
+        file_names: &[&str],
+    ) -> domain::Result<Vec<PathBuf>> {
+        let mut files = WalkBuilder::new(root)
+            .hidden(false)
+            .require_git(false)
+            .filter_entry(|entry| !is_skipped(entry))
+            .build()
+            .map(|entry| entry.map_err(|err| domain::Error::Error(err.to_string())))
+            .filter_map(|entry| match entry {
+                Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => file_names
+                    .iter()
+                    .any(|file_name| entry.file_name() == *file_name)
+                    .then(|| Ok(entry.into_path())),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<domain::Result<Vec<_>>>()?;
+
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+
+    if entry.path_is_symlink() {
+        name == "result" || name.starts_with("result-")
+    } else {
+        entry.file_type().is_some_and(|t| t.is_dir()) && SKIPPED_DIRS.contains(&&*name)
+    }
 }
@@ -0,0 +1,643 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{
+    domain::{
+        self,
+        fs::FileSystem,
+        nix::{
+            flake_url::{self, GitFlakeRef},
+            LockedRev, LockedSource,
+        },
+        process::Process,
+        remote::{Remote, RemoteRefs},
+        revision::{Revision, RevisionProvider},
+    },
+    fs::file_name_of,
+};
+
+/// Looks up revisions in local git repositories, then in a cache of bare mirrors of the inputs'
+/// repositories.
+#[allow(clippy::module_name_repetitions)]
+pub struct GitRevisionProvider<P: Process> {
+    process: P,
+    repositories: Vec<PathBuf>,
+    mirrors: Option<PathBuf>,
+    fetch: bool,
+}
+
+impl<P: Process> GitRevisionProvider<P> {
+    /// When `fetch` is set, missing mirrors are cloned and mirrors that don't have the revision
+    /// are fetched. Without `mirrors` only the local repositories are searched.
+    pub fn new(
+        process: P,
+        repositories: Vec<PathBuf>,
+        mirrors: Option<PathBuf>,
+        fetch: bool,
+    ) -> Self {
+        Self {
+            process,
+            repositories,
+            mirrors,
+            fetch,
+        }
+    }
+
+    fn show(&self, repository: &Path, rev: &LockedRev) -> Option<Revision> {
+        let output = self
+            .process
+            .run(
+                "git",
+                &[
+                    "show",
+                    "--no-patch",
+                    "--format=%cI%n%aI%n%an%n%s",
+                    "--end-of-options",
+                    &format!("{}^{{commit}}", &**rev),
+                ],
+                Some(repository),
+            )
+            .map_err(|err| log::debug!("{} not found in {repository:?}: {err}", &**rev))
+            .ok()?;
+
+        let mut lines = output.lines();
+        let commit_date = OffsetDateTime::parse(lines.next()?, &Rfc3339).ok()?;
+        let author_date = OffsetDateTime::parse(lines.next()?, &Rfc3339).ok()?;
+        let author = lines.next().map(String::from);
+        let subject = lines.next().map(String::from);
+
+        let refs = self
+            .process
+            .run(
+                "git",
+                &[
+                    "for-each-ref",
+                    "--format=%(refname)",
+                    &format!("--contains={}", &**rev),
+                ],
+                Some(repository),
+            )
+            .map(|refs| refs.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        Some(Revision {
+            commit_date,
+            author_date,
+            author,
+            subject,
+            refs,
+        })
+    }
+
+    fn fetch_mirror(&self, source: &LockedSource, mirror: &Path) -> domain::Result<()> {
+        if mirror.exists() {
+            self.process
+                .run("git", &["fetch", "--prune", "origin"], Some(mirror))?;
+        } else {
+            let mirror = mirror.to_str().ok_or(domain::Error::Error(format!(
+                "Couldn't convert path '{}' to str",
+                mirror.display()
+            )))?;
+            self.process.run(
+                "git",
+                &[
+                    "clone",
+                    "--mirror",
+                    "--quiet",
+                    "--",
+                    &source.git_url(),
+                    mirror,
+                ],
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: Process> RevisionProvider for GitRevisionProvider<P> {
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> domain::Result<Option<Revision>> {
+        if !flake_url::is_rev(rev) {
+            return Err(domain::Error::Error(format!(
+                "'{}' is not a git revision",
+                &**rev
+            )));
+        }
+
+        if let Some(revision) = self
+            .repositories
+            .iter()
+            .find_map(|repository| self.show(repository, rev))
+        {
+            return Ok(Some(revision));
+        }
+
+        let Some(mirrors) = &self.mirrors else {
+            return Ok(None);
+        };
+        let mirror = mirrors.join(format!("{}.git", file_name_of(&source.git_url())));
+        if mirror.exists() {
+            if let Some(revision) = self.show(&mirror, rev) {
+                return Ok(Some(revision));
+            }
+        }
+
+        if self.fetch {
+            self.fetch_mirror(source, &mirror)?;
+            return Ok(self.show(&mirror, rev));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Reads the flakes given as `path@rev` as they are at that git revision, without checking the
+/// revision out. Every other file is read from the wrapped file system. Files read at a revision
+/// can't be written.
+#[allow(clippy::module_name_repetitions)]
+pub struct GitFileSystem<FS: FileSystem, P: Process> {
+    fs: FS,
+    process: P,
+    revisions: HashMap<PathBuf, FlakeRevision>,
+}
+
+/// Where to read a flake registered with [`GitFileSystem::with_revision`] from.
+struct FlakeRevision {
+    rev: String,
+    /// The root of the repository the flake lives in
+    repository: PathBuf,
+    /// The flake's directory relative to the root of the repository
+    dir: PathBuf,
+}
+
+impl<FS: FileSystem, P: Process> GitFileSystem<FS, P> {
+    pub fn new(fs: FS, process: P) -> Self {
+        Self {
+            fs,
+            process,
+            revisions: HashMap::new(),
+        }
+    }
+
+    /// Reads the flake at `flake` at the given revision of its repository. `flake` is either the
+    /// flake's directory or its flake.nix or flake.lock, it doesn't need to exist in the working
+    /// tree.
+    pub fn with_revision(mut self, flake: &Path, rev: &str) -> domain::Result<Self> {
+        let flake = self.absolute(flake)?;
+        let dir = match flake.file_name().and_then(|name| name.to_str()) {
+            Some("flake.nix" | "flake.lock") => flake.parent().unwrap_or(&flake).to_path_buf(),
+            _ => flake,
+        };
+
+        // git needs to run in a directory of the working tree, the flake's directory might only
+        // exist at the revision
+        let existing = dir
+            .ancestors()
+            .find(|ancestor| ancestor.is_dir())
+            .ok_or_else(|| {
+                domain::Error::Error(format!("'{}' is not in a git repository", dir.display()))
+            })?;
+        let repository = PathBuf::from(
+            self.process
+                .run("git", &["rev-parse", "--show-toplevel"], Some(existing))?
+                .trim_end(),
+        );
+        let relative = existing
+            .canonicalize()?
+            .join(dir.strip_prefix(existing).unwrap_or(Path::new("")));
+        let relative = relative
+            .strip_prefix(&repository)
+            .map_err(|_| {
+                domain::Error::Error(format!(
+                    "'{}' is not in the git repository at '{}'",
+                    dir.display(),
+                    repository.display()
+                ))
+            })?
+            .to_path_buf();
+
+        self.revisions.insert(
+            dir,
+            FlakeRevision {
+                rev: String::from(rev),
+                repository,
+                dir: relative,
+            },
+        );
+
+        Ok(self)
+    }
+
+    /// Same as [`GitFileSystem::with_revision`] when `flake` is a `path@rev` argument. Returns
+    /// the path without the revision.
+    pub fn with_revision_of(self, flake: &Path) -> domain::Result<(Self, PathBuf)> {
+        match split_revision(flake) {
+            (path, Some(rev)) => Ok((self.with_revision(&path, &rev)?, path)),
+            (path, None) => Ok((self, path)),
+        }
+    }
+
+    fn revision_of(&self, file: &Path) -> domain::Result<Option<&FlakeRevision>> {
+        if self.revisions.is_empty() {
+            return Ok(None);
+        }
+        let dir = self.absolute(file.parent().unwrap_or(Path::new(".")))?;
+
+        Ok(self.revisions.get(&dir))
+    }
+
+    /// Makes `path` absolute and removes its `.` and `..` components without looking at the
+    /// file system, so paths that only exist at a revision can be compared.
+    fn absolute(&self, path: &Path) -> domain::Result<PathBuf> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.fs.current_dir()?.join(path)
+        };
+
+        let mut normalised = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalised.pop();
+                }
+                component => normalised.push(component),
+            }
+        }
+
+        Ok(normalised)
+    }
+}
+
+impl<FS: FileSystem, P: Process> FileSystem for GitFileSystem<FS, P> {
+    fn read_to_string<Q: AsRef<Path>>(&self, p: Q) -> domain::Result<String> {
+        let path = p.as_ref();
+        let Some(revision) = self.revision_of(path)? else {
+            return self.fs.read_to_string(path);
+        };
+
+        let file_name = path.file_name().ok_or(domain::Error::Error(format!(
+            "Cannot determine file name of path '{}'",
+            path.display()
+        )))?;
+        let file = revision.dir.join(file_name);
+        let file = file.to_str().ok_or(domain::Error::Error(format!(
+            "Couldn't convert path '{}' to str",
+            file.display()
+        )))?;
+
+        self.process.run(
+            "git",
+            &[
+                "show",
+                "--end-of-options",
+                &format!("{}:{file}", revision.rev),
+            ],
+            Some(&revision.repository),
+        )
+    }
+
+    fn write<Q: AsRef<Path>>(&self, p: Q, str: &str) -> domain::Result<()> {
+        match self.revision_of(p.as_ref())? {
+            Some(revision) => Err(domain::Error::Error(format!(
+                "'{}' is read at revision {}, it can't be written",
+                p.as_ref().display(),
+                revision.rev
+            ))),
+            None => self.fs.write(p, str),
+        }
+    }
+
+    fn current_dir(&self) -> domain::Result<PathBuf> {
+        self.fs.current_dir()
+    }
+
+    fn find_files<Q: AsRef<Path>>(
+        &self,
+        root: Q,
+        file_names: &[&str],
+    ) -> domain::Result<Vec<PathBuf>> {
+        self.fs.find_files(root, file_names)
+    }
+}
+
+/// Fetches the flake.nix and flake.lock of flakes living in git repositories, e.g.
+/// `github:org/platform` or `git+file:///src/platform?ref=main`, so they can be read like local
+/// flakes. Only the commit of the flake is fetched, without checking it out.
+pub struct GitFlakeFetcher<FS: FileSystem, P: Process> {
+    fs: FS,
+    process: P,
+    dir: PathBuf,
+}
+
+impl<FS: FileSystem, P: Process> GitFlakeFetcher<FS, P> {
+    /// The fetched flakes are stored in `dir`.
+    pub fn new(fs: FS, process: P, dir: PathBuf) -> Self {
+        Self { fs, process, dir }
+    }
+
+    /// Fetches the flake if `flake` is a flake reference and returns the directory of the fetched
+    /// files, local paths are returned as they are.
+    pub fn fetch(&self, flake: &Path) -> domain::Result<PathBuf> {
+        let Some(flake_ref) = flake.to_str() else {
+            return Ok(flake.to_path_buf());
+        };
+        let Some(git_flake_ref) = GitFlakeRef::parse(flake_ref)? else {
+            return Ok(flake.to_path_buf());
+        };
+
+        let target = self.dir.join(file_name_of(flake_ref));
+        let repository = target.join("repository.git");
+        let repository_str = repository.to_str().ok_or(domain::Error::Error(format!(
+            "Couldn't convert path '{}' to str",
+            repository.display()
+        )))?;
+        if !repository.exists() {
+            self.process
+                .run("git", &["init", "--quiet", "--bare", repository_str], None)?;
+        }
+
+        let commit = git_flake_ref
+            .rev
+            .as_deref()
+            .or(git_flake_ref.r#ref.as_deref())
+            .unwrap_or("HEAD");
+        log::debug!("fetching {flake_ref}");
+        self.process.run(
+            "git",
+            &[
+                "fetch",
+                "--quiet",
+                "--depth=1",
+                "--end-of-options",
+                &git_flake_ref.url,
+                commit,
+            ],
+            Some(&repository),
+        )?;
+
+        for file_name in ["flake.nix", "flake.lock"] {
+            let path = match &git_flake_ref.dir {
+                Some(dir) => format!("{}/{file_name}", dir.trim_matches('/')),
+                None => String::from(file_name),
+            };
+            let content = self.process.run(
+                "git",
+                &["show", "--end-of-options", &format!("FETCH_HEAD:{path}")],
+                Some(&repository),
+            )?;
+            self.fs.write(target.join(file_name), &content)?;
+        }
+
+        Ok(target)
+    }
+}
+
+/// Whether the flake is given as a flake reference [`GitFlakeFetcher`] fetches, rather than as a
+/// local path.
+pub fn is_flake_ref(flake: &Path) -> domain::Result<bool> {
+    match flake.to_str() {
+        Some(flake_ref) => Ok(GitFlakeRef::parse(flake_ref)?.is_some()),
+        None => Ok(false),
+    }
+}
+
+/// Splits a `path@rev` argument into the path and the git revision, e.g. `platform@v1.2.0` or
+/// `platform@main`. A trailing `@` keeps a path containing `@` whole, e.g. `flake@v1@`.
+#[must_use]
+pub fn split_revision(path: &Path) -> (PathBuf, Option<String>) {
+    match path.to_str().and_then(|path| path.rsplit_once('@')) {
+        Some((path, "")) if !path.is_empty() => (PathBuf::from(path), None),
+        Some((path, rev)) if !path.is_empty() => (PathBuf::from(path), Some(String::from(rev))),
+        _ => (path.to_path_buf(), None),
+    }
+}
+
+/// Lists the refs of remote repositories using `git ls-remote`.
+pub struct GitRemote<P: Process> {
+    process: P,
+}
+
+impl<P: Process> GitRemote<P> {
+    pub fn new(process: P) -> Self {
+        Self { process }
+    }
+}
+
+impl<P: Process> Remote for GitRemote<P> {
+    fn refs(&self, url: &str) -> domain::Result<RemoteRefs> {
+        let output = self
+            .process
+            .run("git", &["ls-remote", "--end-of-options", url], None)?;
+
+        Ok(parse_ls_remote(&output))
+    }
+}
+
+fn parse_ls_remote(output: &str) -> RemoteRefs {
+    let mut head = None;
+    let mut refs = BTreeMap::new();
+
+    for (rev, name) in output.lines().filter_map(|line| line.split_once('\t')) {
+        if name == "HEAD" {
+            head = Some(String::from(rev));
+        } else if let Some(tag) = name.strip_suffix("^{}") {
+            // the peeled tag points to the tagged commit instead of the tag object
+            refs.insert(String::from(tag), String::from(rev));
+        } else {
+            refs.entry(String::from(name))
+                .or_insert_with(|| String::from(rev));
+        }
+    }
+
+    RemoteRefs::new(head, refs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env::current_dir,
+        path::{Path, PathBuf},
+    };
+
+    use cmd_lib::run_fun;
+    use cmd_lib_macros::run_cmd;
+    use pretty_assertions::assert_eq;
+    use sealed_test::prelude::*;
+    use time::OffsetDateTime;
+
+    use crate::{
+        domain::{
+            fs::FileSystem,
+            nix::{LockedRev, LockedSource},
+            remote::Remote,
+            revision::RevisionProvider,
+        },
+        fs::OsFileSystem,
+        process::OsProcess,
+    };
+
+    use super::{split_revision, GitFileSystem, GitRemote, GitRevisionProvider};
+
+    #[sealed_test]
+    fn test_git_revision_provider_finds_revision_in_local_repository() {
+        run_cmd!(
+            git init --quiet repo;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet --allow-empty -m "initial";
+        )
+        .unwrap();
+        let rev = run_fun!(git -C repo rev-parse HEAD).unwrap();
+        let source = LockedSource::Git {
+            url: String::from("https://example.com/repo.git"),
+        };
+
+        let provider = GitRevisionProvider::new(
+            OsProcess {},
+            vec![current_dir().unwrap().join("repo")],
+            Some(PathBuf::from("mirrors")),
+            false,
+        );
+
+        let revision = provider
+            .revision(&source, &LockedRev::from(rev.as_str()))
+            .unwrap();
+        let unknown = provider
+            .revision(
+                &source,
+                &LockedRev::from("a08e061a4ee8329747d54ddf1566d34c55c895eb"),
+            )
+            .unwrap();
+
+        let revision = revision.unwrap();
+        assert!((OffsetDateTime::now_utc() - revision.commit_date).whole_minutes() < 1);
+        assert_eq!(revision.author.as_deref(), Some("test"));
+        assert_eq!(revision.subject.as_deref(), Some("initial"));
+        assert_eq!(revision.refs.len(), 1);
+        assert!(revision.refs[0].starts_with("refs/heads/"));
+        assert_eq!(unknown, None);
+    }
+
+    #[test]
+    fn test_git_revision_provider_rejects_invalid_revisions() {
+        let provider = GitRevisionProvider::new(
+            OsProcess {},
+            vec![PathBuf::from(".")],
+            Some(PathBuf::from("mirrors")),
+            false,
+        );
+        let source = LockedSource::Git {
+            url: String::from("https://example.com/repo.git"),
+        };
+
+        for rev in ["--output=/tmp/lamina", "HEAD", "a08e061"] {
+            assert!(
+                provider.revision(&source, &LockedRev::from(rev)).is_err(),
+                "{rev} was accepted"
+            );
+        }
+    }
+
+    #[sealed_test]
+    fn test_git_remote_lists_refs() {
+        run_cmd!(
+            git init --quiet --initial-branch=main repo;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet --allow-empty -m "initial";
+            git -C repo tag v0.1.0;
+            git -C repo -c user.name=test -c user.email=test@example.com tag -a v0.2.0 -m "v0.2.0";
+        )
+        .unwrap();
+        let rev = run_fun!(git -C repo rev-parse HEAD).unwrap();
+        let url = format!("file://{}", current_dir().unwrap().join("repo").display());
+
+        let refs = GitRemote::new(OsProcess {}).refs(&url).unwrap();
+
+        assert_eq!(refs.head(), Some(rev.as_str()));
+        assert_eq!(refs.resolve("main"), Some(rev.as_str()));
+        assert_eq!(refs.resolve("refs/tags/v0.2.0"), Some(rev.as_str()));
+        assert_eq!(refs.tags().collect::<Vec<_>>(), vec!["v0.1.0", "v0.2.0"]);
+    }
+
+    #[sealed_test]
+    fn test_git_file_system_reads_files_at_revision() {
+        run_cmd!(
+            git init --quiet repo;
+            mkdir repo/flake;
+            echo "old" > repo/flake/flake.lock;
+            git -C repo add flake/flake.lock;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet -m "old";
+            echo "new" > repo/flake/flake.lock;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet -am "new";
+        )
+        .unwrap();
+        let flake = current_dir().unwrap().join("repo/flake");
+        let path = flake.join("flake.lock");
+        let at = |rev: &str| {
+            GitFileSystem::new(OsFileSystem {}, OsProcess {})
+                .with_revision(&flake, rev)
+                .unwrap()
+        };
+
+        assert_eq!(at("HEAD~1").read_to_string(&path).unwrap(), "old\n");
+        assert_eq!(at("HEAD").read_to_string(&path).unwrap(), "new\n");
+        assert!(at("HEAD").write(&path, "").is_err());
+        assert_eq!(
+            GitFileSystem::new(OsFileSystem {}, OsProcess {})
+                .read_to_string(&path)
+                .unwrap(),
+            "new\n"
+        );
+    }
+
+    #[sealed_test]
+    fn test_git_file_system_reads_flakes_removed_from_the_working_tree() {
+        run_cmd!(
+            git init --quiet repo;
+            mkdir -p repo/flakes/old;
+            echo "old" > repo/flakes/old/flake.lock;
+            git -C repo add flakes/old/flake.lock;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet -m "old";
+            git -C repo rm --quiet -r flakes;
+            git -C repo -c user.name=test -c user.email=test@example.com commit --quiet -m "removed";
+        )
+        .unwrap();
+        let flake = Path::new("repo/flakes/../flakes/old");
+
+        let fs = GitFileSystem::new(OsFileSystem {}, OsProcess {})
+            .with_revision(flake, "HEAD~1")
+            .unwrap();
+
+        assert_eq!(
+            fs.read_to_string(flake.join("flake.lock")).unwrap(),
+            "old\n"
+        );
+    }
+
+    #[test]
+    fn test_split_revision() {
+        assert_eq!(
+            split_revision(Path::new("platform@v1.2.0")),
+            (PathBuf::from("platform"), Some(String::from("v1.2.0")))
+        );
+        assert_eq!(
+            split_revision(Path::new("../platform@release/23.11")),
+            (
+                PathBuf::from("../platform"),
+                Some(String::from("release/23.11"))
+            )
+        );
+        assert_eq!(
+            split_revision(Path::new("flake@v1@")),
+            (PathBuf::from("flake@v1"), None)
+        );
+        assert_eq!(
+            split_revision(Path::new("platform")),
+            (PathBuf::from("platform"), None)
+        );
+    }
+}
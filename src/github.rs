@@ -0,0 +1,238 @@
+use std::env;
+
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    domain::{
+        self,
+        http::HttpClient,
+        nix::{LockedRev, LockedSource},
+        process::Process,
+        revision::{Revision, RevisionProvider},
+    },
+    nix::config::access_tokens,
+};
+
+/// Looks up revisions of GitHub inputs using the GitHub REST API.
+#[allow(clippy::module_name_repetitions)]
+pub struct GitHubRevisionProvider<H: HttpClient> {
+    http: H,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl<H: HttpClient> GitHubRevisionProvider<H> {
+    pub fn new(http: H, api_url: &str, token: Option<String>) -> Self {
+        Self {
+            http,
+            api_url: String::from(api_url.trim_end_matches('/')),
+            token,
+        }
+    }
+}
+
+/// The token used to authenticate against the GitHub API: `GITHUB_TOKEN`, or the `github.com`
+/// entry of Nix's `access-tokens` setting.
+pub fn github_token<P: Process>(process: &P) -> Option<String> {
+    env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or_else(|| access_tokens(process).remove("github.com"))
+}
+
+#[derive(Deserialize)]
+struct CommitResponse {
+    commit: Commit,
+}
+
+#[derive(Deserialize)]
+struct Commit {
+    author: Signature,
+    committer: Signature,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Signature {
+    name: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    date: OffsetDateTime,
+}
+
+impl<H: HttpClient> RevisionProvider for GitHubRevisionProvider<H> {
+    fn revision(&self, source: &LockedSource, rev: &LockedRev) -> domain::Result<Option<Revision>> {
+        let LockedSource::GitHub { owner, repo } = source else {
+            return Ok(None);
+        };
+
+        let url = format!("{}/repos/{owner}/{repo}/commits/{}", self.api_url, &**rev);
+        let authorization = self.token.as_ref().map(|token| format!("Bearer {token}"));
+        let mut headers = vec![
+            ("Accept", "application/vnd.github+json"),
+            ("X-GitHub-Api-Version", "2022-11-28"),
+        ];
+        if let Some(authorization) = &authorization {
+            headers.push(("Authorization", authorization));
+        }
+
+        let response = self.http.get(&url, &headers)?;
+
+        match response.status {
+            200 => {
+                let CommitResponse { commit } = serde_json::from_str(&response.body)
+                    .map_err(|err| domain::Error::HttpError(format!("{url}: {err}")))?;
+
+                Ok(Some(Revision {
+                    commit_date: commit.committer.date,
+                    author_date: commit.author.date,
+                    author: commit.author.name,
+                    subject: commit.message.lines().next().map(String::from),
+                    refs: vec![],
+                }))
+            }
+            404 | 422 => Ok(None),
+            status => Err(domain::Error::HttpError(format!(
+                "{url} responded with {status}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use pretty_assertions::assert_eq;
+    use time::OffsetDateTime;
+    use tiny_http::{Header, Response, Server};
+
+    use crate::{
+        domain::{
+            nix::{LockedRev, LockedSource},
+            revision::{Revision, RevisionProvider},
+        },
+        http::UreqHttpClient,
+    };
+
+    use super::GitHubRevisionProvider;
+
+    const REV: &str = "3892194d7b3293de8b30f1d19e2af45ba41ba8fd";
+
+    fn github_source() -> LockedSource {
+        LockedSource::GitHub {
+            owner: String::from("voidcontext"),
+            repo: String::from("nix-rust-utils"),
+        }
+    }
+
+    /// Serves the given number of requests, responding with the commit of `REV`, or 404
+    fn mock_server(requests: usize) -> (String, thread::JoinHandle<Vec<Option<String>>>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+
+        let handle = thread::spawn(move || {
+            server
+                .incoming_requests()
+                .take(requests)
+                .map(|request| {
+                    let authorization = request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("Authorization"))
+                        .map(|header| header.value.to_string());
+
+                    let response = if request.url()
+                        == format!("/repos/voidcontext/nix-rust-utils/commits/{REV}")
+                    {
+                        Response::from_string(
+                            r#"{
+                                "sha": "3892194d7b3293de8b30f1d19e2af45ba41ba8fd",
+                                "commit": {
+                                    "author": {"name": "a", "date": "2023-05-25T07:33:20Z"},
+                                    "committer": {"name": "c", "date": "2023-05-31T21:53:20Z"},
+                                    "message": "Release v0.3.0"
+                                }
+                            }"#,
+                        )
+                        .with_header("Content-Type: application/json".parse::<Header>().unwrap())
+                    } else {
+                        Response::from_string(r#"{"message": "Not Found"}"#).with_status_code(404)
+                    };
+                    request.respond(response).unwrap();
+
+                    authorization
+                })
+                .collect()
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_github_revision_provider_queries_commit() {
+        let (url, server) = mock_server(2);
+        let provider = GitHubRevisionProvider::new(
+            UreqHttpClient::default(),
+            &url,
+            Some(String::from("ghp_secret")),
+        );
+
+        let revision = provider
+            .revision(&github_source(), &LockedRev::from(REV))
+            .unwrap();
+        let unknown = provider
+            .revision(
+                &github_source(),
+                &LockedRev::from("a08e061a4ee8329747d54ddf1566d34c55c895eb"),
+            )
+            .unwrap();
+
+        #[allow(clippy::unreadable_literal)]
+        let expected = Revision {
+            commit_date: OffsetDateTime::from_unix_timestamp(1685570000).unwrap(),
+            author_date: OffsetDateTime::from_unix_timestamp(1685000000).unwrap(),
+            author: Some(String::from("a")),
+            subject: Some(String::from("Release v0.3.0")),
+            refs: vec![],
+        };
+        assert_eq!(revision, Some(expected));
+        assert_eq!(unknown, None);
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                Some(String::from("Bearer ghp_secret")),
+                Some(String::from("Bearer ghp_secret"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_revision_provider_skips_other_sources() {
+        let provider =
+            GitHubRevisionProvider::new(UreqHttpClient::default(), "http://[::1]:1", None);
+
+        let revision = provider
+            .revision(
+                &LockedSource::Git {
+                    url: String::from("https://example.com/repo.git"),
+                },
+                &LockedRev::from(REV),
+            )
+            .unwrap();
+
+        assert_eq!(revision, None);
+    }
+
+    #[test]
+    fn test_github_revision_provider_fails_when_offline() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        drop(server);
+        let provider = GitHubRevisionProvider::new(UreqHttpClient::default(), &url, None);
+
+        let result = provider.revision(&github_source(), &LockedRev::from(REV));
+
+        assert!(matches!(result, Err(crate::domain::Error::HttpError(_))));
+    }
+}
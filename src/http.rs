@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use crate::domain::{
+    self,
+    http::{HttpClient, HttpResponse},
+};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct UreqHttpClient {
+    agent: ureq::Agent,
+}
+
+impl Default for UreqHttpClient {
+    fn default() -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(10))
+                .user_agent(concat!("lamina/", env!("CARGO_PKG_VERSION")))
+                .build(),
+        }
+    }
+}
+
+impl HttpClient for UreqHttpClient {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> domain::Result<HttpResponse> {
+        let request = headers
+            .iter()
+            .fold(self.agent.get(url), |request, (name, value)| {
+                request.set(name, value)
+            });
+
+        log::debug!("GET {url}");
+
+        let response = match request.call() {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(domain::Error::HttpError(err.to_string())),
+        };
+
+        Ok(HttpResponse {
+            status: response.status(),
+            body: response.into_string()?,
+        })
+    }
+}
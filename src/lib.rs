@@ -1,4 +1,10 @@
+pub mod cache;
 pub mod console;
 pub mod domain;
+pub mod forge;
 pub mod fs;
+pub mod git;
+pub mod github;
+pub mod http;
 pub mod nix;
+pub mod process;
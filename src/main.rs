@@ -1,30 +1,416 @@
-use std::env::current_dir;
+use std::{
+    collections::HashMap,
+    env::current_dir,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use crate::cli::Args;
 use clap::Parser;
-use cli::Command;
+use cli::{Column, Command, DiffFormat, Format, GraphFormat, SortBy};
 use lamina::{
+    cache::{CacheMode, CachedRevisionProvider},
     console::OsConsole,
     domain::{
         self,
-        commands::{self, SyncInputNames},
+        commands::{
+            self, BranchPattern, BumpTarget, LastModifiedOptions, MaxAge, OutputFormat,
+            SyncInputNames,
+        },
+        nix::Flake as _,
     },
-    fs::OsFileSystem,
+    forge::{forge_tokens, Forge, ForgeRevisionProvider},
+    fs::{cache_dir, OsFileSystem},
+    git::{is_flake_ref, GitFileSystem, GitFlakeFetcher, GitRemote, GitRevisionProvider},
+    github::{github_token, GitHubRevisionProvider},
+    http::UreqHttpClient,
     nix::{Flake, FlakeLockMapperImpl},
+    process::OsProcess,
 };
 use log::LevelFilter::{Debug, Info};
 use simple_logger::SimpleLogger;
+use time::OffsetDateTime;
 
 mod cli;
 
-fn main() -> lamina::domain::Result<()> {
+fn main() -> ExitCode {
     let args = Args::parse();
 
     let log_level = if args.debug { Debug } else { Info };
     SimpleLogger::new().with_level(log_level).init().unwrap();
 
-    match args.command {
-        Command::LastModified => {
+    match run(args.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn run(command: Command) -> lamina::domain::Result<()> {
+    match command {
+        Command::LastModified {
+            flake: flake_path,
+            in_tree,
+            transitive,
+            format,
+            columns,
+            sort,
+            max_age,
+            git_repo,
+            git_mirrors,
+            fetch_mirrors,
+            github_api_url,
+            forge,
+            no_cache,
+            refresh,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+            let options = LastModifiedOptions {
+                format: format.into(),
+                columns: columns.into_iter().map(Into::into).collect(),
+                sort: sort.into(),
+                transitive,
+                max_age: MaxAge::parse(&max_age)?,
+                now: OffsetDateTime::now_utc(),
+            };
+            let looks_up_revisions = options
+                .columns
+                .iter()
+                .any(|column| column.looks_up_revision());
+            let revisions = CachedRevisionProvider::new(
+                (
+                    (
+                        GitRevisionProvider::new(
+                            OsProcess {},
+                            git_repo,
+                            match git_mirrors {
+                                Some(dir) => Some(dir),
+                                None if looks_up_revisions => Some(cache_dir()?.join("git")),
+                                None => None,
+                            },
+                            fetch_mirrors,
+                        ),
+                        GitHubRevisionProvider::new(
+                            UreqHttpClient::default(),
+                            &github_api_url,
+                            looks_up_revisions
+                                .then(|| github_token(&OsProcess {}))
+                                .flatten(),
+                        ),
+                    ),
+                    ForgeRevisionProvider::new(
+                        UreqHttpClient::default(),
+                        forge
+                            .iter()
+                            .map(|forge| forge.parse())
+                            .collect::<domain::Result<Vec<Forge>>>()?,
+                        if looks_up_revisions {
+                            forge_tokens(&OsProcess {})
+                        } else {
+                            HashMap::new()
+                        },
+                    ),
+                ),
+                if no_cache || !looks_up_revisions {
+                    CacheMode::Disabled
+                } else if refresh {
+                    CacheMode::Refresh(cache_dir()?.join("revisions"))
+                } else {
+                    CacheMode::Enabled(cache_dir()?.join("revisions"))
+                },
+            );
+
+            match in_tree {
+                Some(root) => commands::last_modified_all(
+                    &flake.discover(root)?,
+                    &options,
+                    &flake,
+                    &revisions,
+                    &console,
+                ),
+                None => commands::last_modified(
+                    flake_path.as_deref(),
+                    &options,
+                    &flake,
+                    &revisions,
+                    &console,
+                ),
+            }
+        }
+        Command::Outdated {
+            flake: flake_path,
+            json,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+            let remote = GitRemote::new(OsProcess {});
+
+            commands::outdated(flake_path.as_deref(), json, &flake, &remote, &console)
+        }
+        Command::Bump {
+            input,
+            flake: flake_path,
+            minor,
+            patch,
+            major: _,
+            to,
+            dry_run,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+            let remote = GitRemote::new(OsProcess {});
+            let target = match to {
+                Some(tag) => BumpTarget::To(tag),
+                None if patch => BumpTarget::Patch,
+                None if minor => BumpTarget::Minor,
+                None => BumpTarget::Major,
+            };
+
+            commands::bump(
+                &flake_path.map_or_else(current_dir, Ok)?,
+                &input,
+                &target,
+                dry_run,
+                &flake,
+                &remote,
+                &console,
+            )
+        }
+        Command::BumpBranch {
+            input,
+            pattern,
+            mut flakes,
+            in_tree,
+            branches,
+            dry_run,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+            let remote = GitRemote::new(OsProcess {});
+
+            if let Some(root) = in_tree {
+                flakes.extend(flake.discover(root)?);
+            } else if flakes.is_empty() {
+                flakes.push(current_dir()?);
+            }
+
+            commands::bump_branch(
+                &flakes,
+                &input,
+                &BranchPattern::parse(&pattern)?,
+                branches.as_deref(),
+                dry_run,
+                &flake,
+                &remote,
+                &console,
+            )
+        }
+        Command::Diff {
+            old,
+            new,
+            flake: flake_path,
+            format,
+        } => {
+            let console = OsConsole {};
+
+            let git_fs = || GitFileSystem::new(OsFileSystem {}, OsProcess {});
+            let load = |fs: GitFileSystem<OsFileSystem, OsProcess>, path: &Path| {
+                Flake::new(fs, FlakeLockMapperImpl {}).load_lock_from(path)
+            };
+
+            let range = old
+                .split_once("..")
+                .filter(|(old_rev, new_rev)| !old_rev.is_empty() && !new_rev.is_empty());
+            let (old, new) = match (new, range) {
+                (Some(new), _) => {
+                    let (old_fs, old) = git_fs().with_revision_of(Path::new(&old))?;
+                    let (new_fs, new) = git_fs().with_revision_of(&new)?;
+                    (load(old_fs, &old)?, load(new_fs, &new)?)
+                }
+                (None, Some((old_rev, new_rev))) => {
+                    let flake_path = match flake_path {
+                        Some(flake_path) => flake_path,
+                        None => current_dir()?,
+                    };
+                    (
+                        load(git_fs().with_revision(&flake_path, old_rev)?, &flake_path)?,
+                        load(git_fs().with_revision(&flake_path, new_rev)?, &flake_path)?,
+                    )
+                }
+                (None, None) => return Err(domain::Error::Error(String::from(
+                    "diff needs two flake.lock files or a range of revisions, e.g. HEAD~1..HEAD",
+                ))),
+            };
+
+            commands::diff(&old, &new, format.into(), &console)
+        }
+        Command::Graph {
+            flake: flake_path,
+            format,
+            collapse,
+            highlight,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::graph(
+                flake_path.as_deref(),
+                format.into(),
+                collapse,
+                highlight,
+                &flake,
+                &console,
+            )
+        }
+        Command::Dups {
+            flake: flake_path,
+            json,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::dups(flake_path.as_deref(), json, &flake, &console)
+        }
+        Command::Dedupe {
+            flake: flake_path,
+            source,
+            dry_run,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::dedupe(
+                &flake_path.map_or_else(current_dir, Ok)?,
+                source.as_deref(),
+                dry_run,
+                &flake,
+                &console,
+            )
+        }
+        Command::Explain {
+            input,
+            flake: flake_path,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::explain(
+                &flake_path.map_or_else(current_dir, Ok)?,
+                &input,
+                &flake,
+                &console,
+            )
+        }
+        Command::Verify { flake: flake_path } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::verify(&flake_path.map_or_else(current_dir, Ok)?, &flake, &console)
+        }
+        Command::GcLock {
+            check,
+            flake: flake_path,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::gc_lock(
+                &flake_path.map_or_else(current_dir, Ok)?,
+                check,
+                &flake,
+                &console,
+            )
+        }
+        Command::UnusedInputs {
+            remove,
+            flake: flake_path,
+        } => {
+            let fs = OsFileSystem {};
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+
+            commands::unused_inputs(
+                &flake_path.map_or_else(current_dir, Ok)?,
+                remove,
+                &flake,
+                &console,
+            )
+        }
+        Command::Status {
+            src_flake,
+            dst_flake,
+            by_source,
+        } => {
+            let (fs, src_flake) =
+                GitFileSystem::new(OsFileSystem {}, OsProcess {}).with_revision_of(&src_flake)?;
+            let (fs, dst_flake) = fs.with_revision_of(&dst_flake.map_or_else(current_dir, Ok)?)?;
+            let lock_mapper = FlakeLockMapperImpl {};
+
+            let console = OsConsole {};
+
+            let flake = Flake::new(fs, lock_mapper);
+            let sync_service = domain::nix::SyncServiceImpl {};
+            commands::status(
+                &src_flake,
+                &dst_flake,
+                by_source,
+                &flake,
+                &sync_service,
+                &console,
+            )
+        }
+        Command::Matrix {
+            mut flakes,
+            in_tree,
+            json,
+        } => {
             let fs = OsFileSystem {};
             let lock_mapper = FlakeLockMapperImpl {};
 
@@ -32,27 +418,40 @@ fn main() -> lamina::domain::Result<()> {
 
             let flake = Flake::new(fs, lock_mapper);
 
-            commands::last_modified(&flake, &console)
+            if let Some(root) = in_tree {
+                flakes.extend(flake.discover(root)?);
+            }
+
+            commands::matrix(&flakes, json, OffsetDateTime::now_utc(), &flake, &console)
         }
         Command::Sync {
             src_flake,
             src_input_name,
             dst_flake,
             dst_input_name,
+            into_tree,
+            check,
         } => {
-            let fs = OsFileSystem {};
+            let src_flake = fetch(&src_flake)?;
+            let (fs, src_flake) =
+                GitFileSystem::new(OsFileSystem {}, OsProcess {}).with_revision_of(&src_flake)?;
             let lock_mapper = FlakeLockMapperImpl {};
 
             let flake = Flake::new(fs, lock_mapper);
             let sync_service = domain::nix::SyncServiceImpl {};
-            commands::sync(
+            let dst_flake = dst_flake
+                .unwrap_or_else(|| current_dir().expect("Couldn't determine the current dir"));
+            let inputs = [SyncInputNames::source_and_destination(
+                src_input_name.clone(),
+                dst_input_name.unwrap_or(src_input_name),
+            )];
+
+            sync(
                 &src_flake,
-                &(dst_flake
-                    .unwrap_or_else(|| current_dir().expect("Couldn't determine the current dir"))),
-                &[SyncInputNames::source_and_destination(
-                    src_input_name.clone(),
-                    dst_input_name.unwrap_or(src_input_name),
-                )],
+                &dst_flake,
+                &inputs,
+                into_tree,
+                check,
                 &flake,
                 &sync_service,
             )
@@ -61,22 +460,136 @@ fn main() -> lamina::domain::Result<()> {
             src_flake,
             dst_flake,
             inputs,
+            into_tree,
+            check,
         } => {
-            let fs = OsFileSystem {};
+            let src_flake = fetch(&src_flake)?;
+            let (fs, src_flake) =
+                GitFileSystem::new(OsFileSystem {}, OsProcess {}).with_revision_of(&src_flake)?;
             let lock_mapper = FlakeLockMapperImpl {};
 
             let flake = Flake::new(fs, lock_mapper);
             let sync_service = domain::nix::SyncServiceImpl {};
-            commands::sync(
+            let inputs = inputs
+                .iter()
+                .map(|name| SyncInputNames::same(name.clone()))
+                .collect::<Vec<_>>();
+
+            sync(
                 &src_flake,
                 &dst_flake,
-                &(inputs
-                    .iter()
-                    .map(|name| SyncInputNames::same(name.clone()))
-                    .collect::<Vec<_>>()),
+                &inputs,
+                into_tree,
+                check,
                 &flake,
                 &sync_service,
             )
         }
     }
 }
+
+/// Fetches the flake when it's a flake reference, so only those need the cache directory.
+fn fetch(flake: &Path) -> lamina::domain::Result<PathBuf> {
+    if is_flake_ref(flake)? {
+        GitFlakeFetcher::new(OsFileSystem {}, OsProcess {}, cache_dir()?.join("flakes"))
+            .fetch(flake)
+    } else {
+        Ok(flake.to_path_buf())
+    }
+}
+
+fn sync<F: domain::nix::Flake, S: domain::nix::SyncService>(
+    src_flake: &Path,
+    dst_flake: &Path,
+    inputs: &[SyncInputNames],
+    into_tree: bool,
+    check: bool,
+    flake: &F,
+    sync_service: &S,
+) -> lamina::domain::Result<()> {
+    let console = OsConsole {};
+
+    match (into_tree, check) {
+        (true, false) => commands::sync_all(
+            &src_flake.canonicalize()?,
+            &flake.discover(dst_flake.canonicalize()?)?,
+            inputs,
+            flake,
+            sync_service,
+        ),
+        (true, true) => commands::check_sync_all(
+            &src_flake.canonicalize()?,
+            &flake.discover(dst_flake.canonicalize()?)?,
+            inputs,
+            flake,
+            sync_service,
+            &console,
+        ),
+        (false, false) => commands::sync(src_flake, dst_flake, inputs, flake, sync_service),
+        (false, true) => {
+            commands::check_sync(src_flake, dst_flake, inputs, flake, sync_service, &console)
+        }
+    }
+}
+
+impl From<GraphFormat> for commands::GraphFormat {
+    fn from(value: GraphFormat) -> Self {
+        match value {
+            GraphFormat::Dot => commands::GraphFormat::Dot,
+            GraphFormat::Mermaid => commands::GraphFormat::Mermaid,
+            GraphFormat::Json => commands::GraphFormat::Json,
+        }
+    }
+}
+
+impl From<DiffFormat> for OutputFormat {
+    fn from(value: DiffFormat) -> Self {
+        match value {
+            DiffFormat::Table => OutputFormat::Table,
+            DiffFormat::Json => OutputFormat::Json,
+            DiffFormat::Markdown => OutputFormat::Markdown,
+        }
+    }
+}
+
+impl From<Format> for OutputFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Table => OutputFormat::Table,
+            Format::Json => OutputFormat::Json,
+            Format::Csv => OutputFormat::Csv,
+            Format::Markdown => OutputFormat::Markdown,
+            Format::Tsv => OutputFormat::Tsv,
+        }
+    }
+}
+
+impl From<Column> for commands::Column {
+    fn from(column: Column) -> Self {
+        match column {
+            Column::Input => commands::Column::Input,
+            Column::Type => commands::Column::Type,
+            Column::Source => commands::Column::Source,
+            Column::Ref => commands::Column::Ref,
+            Column::Rev => commands::Column::Rev,
+            Column::Pinned => commands::Column::Pinned,
+            Column::Age => commands::Column::Age,
+            Column::LastModified => commands::Column::LastModified,
+            Column::CommitDate => commands::Column::CommitDate,
+            Column::AuthorDate => commands::Column::AuthorDate,
+            Column::Author => commands::Column::Author,
+            Column::Subject => commands::Column::Subject,
+            Column::Refs => commands::Column::Refs,
+        }
+    }
+}
+
+impl From<SortBy> for commands::SortBy {
+    fn from(sort: SortBy) -> Self {
+        match sort {
+            SortBy::Name => commands::SortBy::Name,
+            SortBy::Age => commands::SortBy::Age,
+            SortBy::Type => commands::SortBy::Type,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::domain::process::Process;
+
+/// Reads the `access-tokens` setting of Nix, a map of host names to tokens. Empty when Nix isn't
+/// available or no tokens are configured.
+pub fn access_tokens<P: Process>(process: &P) -> HashMap<String, String> {
+    let setting = process
+        .run("nix", &["config", "show", "access-tokens"], None)
+        .or_else(|_| {
+            process.run("nix", &["show-config"], None).map(|config| {
+                config
+                    .lines()
+                    .find_map(|line| line.strip_prefix("access-tokens = "))
+                    .map(String::from)
+                    .unwrap_or_default()
+            })
+        });
+
+    match setting {
+        Ok(setting) => parse_access_tokens(&setting),
+        Err(err) => {
+            log::debug!("couldn't read the access-tokens setting of nix: {err}");
+            HashMap::new()
+        }
+    }
+}
+
+/// Parses whitespace separated `host=token` pairs.
+#[must_use]
+pub fn parse_access_tokens(setting: &str) -> HashMap<String, String> {
+    setting
+        .split_whitespace()
+        .filter_map(|token| token.split_once('='))
+        .map(|(host, token)| (String::from(host), String::from(token)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::parse_access_tokens;
+
+    #[test]
+    fn test_parse_access_tokens() {
+        assert_eq!(
+            parse_access_tokens("github.com=ghp_abc gitlab.example.com=PAT:glpat-xyz\n"),
+            HashMap::from([
+                (String::from("github.com"), String::from("ghp_abc")),
+                (
+                    String::from("gitlab.example.com"),
+                    String::from("PAT:glpat-xyz")
+                ),
+            ])
+        );
+        assert_eq!(parse_access_tokens("\n"), HashMap::new());
+    }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, process::Command};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    process::Command,
+};
 
 use crate::{
     domain::{self, fs::FileSystem, nix::FlakeNix},
@@ -49,6 +53,32 @@ impl<FS: FileSystem, M: FlakeLockMapper> domain::nix::Flake for Flake<FS, M> {
         Ok(FlakeNix::new(content))
     }
 
+    fn discover<P: AsRef<std::path::Path>>(&self, root: P) -> domain::Result<Vec<PathBuf>> {
+        let files = self
+            .fs
+            .find_files(root.as_ref(), &["flake.nix", "flake.lock"])?;
+        let dirs_of = |file_name: &str| {
+            files
+                .iter()
+                .filter(|file| file.file_name().is_some_and(|name| name == file_name))
+                .filter_map(|file| file.parent())
+                .collect::<Vec<_>>()
+        };
+        let lock_dirs = dirs_of("flake.lock").into_iter().collect::<HashSet<_>>();
+
+        Ok(dirs_of("flake.nix")
+            .into_iter()
+            .filter(|dir| {
+                let locked = lock_dirs.contains(dir);
+                if !locked {
+                    log::debug!("skipping {dir:?}: flake.lock is missing");
+                }
+                locked
+            })
+            .map(std::path::Path::to_path_buf)
+            .collect())
+    }
+
     fn write<P: AsRef<std::path::Path>>(&self, p: P, flake: &FlakeNix) -> domain::Result<()> {
         let flake_file = ensure_file(p.as_ref(), "flake.nix")?;
 
@@ -76,6 +106,55 @@ impl<FS: FileSystem, M: FlakeLockMapper> domain::nix::Flake for Flake<FS, M> {
 
         Ok(())
     }
+
+    fn lock<P: AsRef<std::path::Path>>(&self, p: P) -> domain::Result<()> {
+        let path = p.as_ref().to_str().ok_or(domain::Error::Error(format!(
+            "Couldn't convert path '{}' to str",
+            p.as_ref().display()
+        )))?;
+
+        let mut cmd = Command::new("nix");
+        cmd.args(["flake", "lock", path]);
+
+        log::debug!("running command: {:?}", cmd);
+
+        let status = cmd.status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(domain::Error::ProcessError(format!(
+                "nix flake lock {path} exited with {status}"
+            )))
+        }
+    }
+
+    fn remove_lock_nodes<P: AsRef<std::path::Path>>(
+        &self,
+        p: P,
+        nodes: &[String],
+    ) -> domain::Result<()> {
+        let lock_file = ensure_file(p.as_ref(), "flake.lock")?;
+        let invalid = |err: serde_json::Error| domain::Error::InvalidFlakeLock {
+            reason: err.to_string(),
+        };
+
+        // the lock is edited as plain json to keep the fields the lock model doesn't know about
+        let mut lock =
+            serde_json::from_str::<serde_json::Value>(&self.fs.read_to_string(lock_file.clone())?)
+                .map_err(invalid)?;
+        let root = lock["root"].as_str().map(String::from);
+        if let Some(lock_nodes) = lock["nodes"].as_object_mut() {
+            for node in nodes {
+                if root.as_ref() != Some(node) {
+                    lock_nodes.remove(node);
+                }
+            }
+        }
+
+        // nix writes flake.lock with sorted keys, 2 spaces indentation and a trailing newline
+        let json = serde_json::to_string_pretty(&lock).map_err(invalid)?;
+        self.fs.write(lock_file, &format!("{json}\n"))
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -240,7 +319,9 @@ fn find_root(f: &FlakeLock) -> Option<domain::nix::RootNode> {
                             InputReference::Alias(input) => {
                                 domain::nix::InputReference::Alias(input.clone())
                             }
-                            InputReference::Path(_) => panic!("This shouldn't happen"),
+                            InputReference::Path(path) => {
+                                domain::nix::InputReference::Path(path.clone())
+                            }
                         },
                     )
                 })
@@ -1,3 +1,4 @@
+pub mod config;
 mod flake;
 pub mod flake_lock;
 
@@ -0,0 +1,31 @@
+use std::{path::Path, process::Command};
+
+use crate::domain::{self, process::Process};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct OsProcess {}
+
+impl Process for OsProcess {
+    fn run(&self, program: &str, args: &[&str], dir: Option<&Path>) -> domain::Result<String> {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        if let Some(dir) = dir {
+            cmd.current_dir(dir);
+        }
+
+        log::debug!("running command: {:?}", cmd);
+
+        let output = cmd.output()?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(domain::Error::ProcessError(format!(
+                "{program} {} exited with {}: {}",
+                args.join(" "),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+}
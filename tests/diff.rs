@@ -0,0 +1,92 @@
+use std::{
+    env::current_dir,
+    fs,
+    io::{self, Write},
+};
+
+use assert_cmd::Command;
+use cmd_lib_macros::run_cmd;
+use sealed_test::prelude::*;
+
+const NEW_REV: &str = "8b3ad2fe8e06c2189908b7164f2f0bf2126b83b1";
+
+/// Commits the oneline fixture as `src` and `dst`, then moves `nixpkgs-github` of `src` to a new
+/// revision in a second commit.
+fn setup() {
+    run_cmd!(
+        git init --quiet;
+        cp -r oneline src;
+        cp -r oneline dst;
+        git add src dst;
+        git -c user.name=test -c user.email=test@example.com commit --quiet -m "initial";
+    )
+    .unwrap();
+    let mut lock: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string("src/flake.lock").unwrap()).unwrap();
+    let locked = &mut lock["nodes"]["nixpkgs-github"]["locked"];
+    locked["rev"] = serde_json::Value::from(NEW_REV);
+    locked["lastModified"] = serde_json::Value::from(1_688_495_847 + 2 * 86400);
+    fs::write("src/flake.lock", lock.to_string()).unwrap();
+    run_cmd!(
+        git -c user.name=test -c user.email=test@example.com commit --quiet -am "update";
+    )
+    .unwrap();
+}
+
+fn lamina(args: &[&str]) -> String {
+    let output = Command::cargo_bin("lamina")
+        .unwrap()
+        .args(args)
+        .current_dir(current_dir().unwrap())
+        .unwrap();
+
+    io::stdout().write_all(&output.stdout).unwrap();
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[sealed_test(files=["tests/fixtures/oneline"])]
+fn test_diff_git_revisions() {
+    setup();
+
+    let stdout = lamina(&[
+        "diff",
+        "HEAD~1..HEAD",
+        "--flake",
+        "src",
+        "--format",
+        "markdown",
+    ]);
+
+    assert_eq!(
+        stdout,
+        "| input | change | source | ref | rev | last_modified |\n\
+         |---|---|---|---|---|---|\n\
+         | nixpkgs-github | changed | github:Nixos/nixpkgs | release-23.05 | dc1517e -> 8b3ad2f | +2 days |\n"
+    );
+}
+
+#[sealed_test(files=["tests/fixtures/oneline"])]
+fn test_status_of_source_at_git_revision() {
+    setup();
+
+    let current = lamina(&["status", "src", "dst"]);
+    let at_revision = lamina(&["status", "src@HEAD~1", "dst"]);
+
+    let nixpkgs_github = |stdout: &str| {
+        stdout
+            .lines()
+            .find(|line| line.contains("nixpkgs-github"))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+    };
+    assert_eq!(nixpkgs_github(&current)[2], "differs");
+    assert_eq!(nixpkgs_github(&at_revision)[2], "same");
+}
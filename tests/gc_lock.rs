@@ -0,0 +1,29 @@
+use std::fs;
+
+use assert_cmd::Command;
+use sealed_test::prelude::*;
+
+#[sealed_test(files=["tests/fixtures/oneline"])]
+fn test_gc_lock_removes_orphans_and_keeps_the_rest_of_the_lock() {
+    let original = fs::read_to_string("oneline/flake.lock").unwrap();
+    let mut lock: serde_json::Value = serde_json::from_str(&original).unwrap();
+    let mut orphan = lock["nodes"]["nixpkgs-github"].clone();
+    orphan["locked"]["narHash"] = serde_json::Value::from("sha256-orphan");
+    lock["nodes"]["orphan"] = orphan;
+    fs::write("oneline/flake.lock", lock.to_string()).unwrap();
+
+    Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["gc-lock", "--check", "oneline"])
+        .assert()
+        .code(5);
+
+    Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["gc-lock", "oneline"])
+        .assert()
+        .success()
+        .stdout("removed 1 node from flake.lock:\n  orphan (github:Nixos/nixpkgs dc1517e)\n");
+
+    assert_eq!(fs::read_to_string("oneline/flake.lock").unwrap(), original);
+}
@@ -0,0 +1,47 @@
+use std::{
+    env::current_dir,
+    fs,
+    io::{self, Write},
+};
+
+use assert_cmd::Command;
+
+use sealed_test::prelude::*;
+
+#[sealed_test(files=["tests/fixtures/nested", "tests/fixtures/oneline"])]
+fn test_last_modified_in_tree() {
+    let working_dir = current_dir().unwrap();
+
+    for dir in ["ignored", ".direnv/flake", "no-lock"] {
+        fs::create_dir_all(working_dir.join(dir)).unwrap();
+        fs::copy("oneline/flake.nix", working_dir.join(dir).join("flake.nix")).unwrap();
+        if dir != "no-lock" {
+            fs::copy(
+                "oneline/flake.lock",
+                working_dir.join(dir).join("flake.lock"),
+            )
+            .unwrap();
+        }
+    }
+    fs::write(working_dir.join(".gitignore"), "ignored/\n").unwrap();
+
+    let output = Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["last-modified", "--in-tree", "."])
+        .current_dir(&working_dir)
+        .unwrap();
+
+    io::stdout().write_all(&output.stdout).unwrap();
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let flakes = stdout
+        .lines()
+        .filter(|line| line.starts_with("./"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(flakes, vec!["./nested", "./oneline"]);
+    assert!(stdout.contains("nixpkgs-indirect-ref"));
+}
@@ -0,0 +1,75 @@
+use std::{
+    env::current_dir,
+    fs,
+    io::{self, Write},
+};
+
+use assert_cmd::Command;
+use cmd_lib::run_fun;
+use cmd_lib_macros::run_cmd;
+use sealed_test::prelude::*;
+
+#[sealed_test]
+fn test_outdated_with_local_git_repository() {
+    let working_dir = current_dir().unwrap();
+    run_cmd!(
+        git init --quiet --initial-branch=main upstream;
+        git -C upstream -c user.name=test -c user.email=test@example.com commit --quiet --allow-empty -m "v0.1.0";
+        git -C upstream tag v0.1.0;
+    )
+    .unwrap();
+    let locked_rev = run_fun!(git -C upstream rev-parse HEAD).unwrap();
+    run_cmd!(
+        git -C upstream -c user.name=test -c user.email=test@example.com commit --quiet --allow-empty -m "v0.2.0";
+        git -C upstream tag v0.2.0;
+    )
+    .unwrap();
+    let latest_rev = run_fun!(git -C upstream rev-parse HEAD).unwrap();
+
+    let url = format!("file://{}", working_dir.join("upstream").display());
+    let node = |r#ref: &str| {
+        format!(
+            r#"{{
+              "locked": {{ "lastModified": 1685572332, "ref": "{ref}", "rev": "{locked_rev}", "type": "git", "url": "{url}" }},
+              "original": {{ "ref": "{ref}", "type": "git", "url": "{url}" }}
+            }}"#
+        )
+    };
+    fs::create_dir(working_dir.join("flake")).unwrap();
+    fs::write(
+        working_dir.join("flake/flake.lock"),
+        format!(
+            r#"{{
+              "nodes": {{
+                "branch": {},
+                "tag": {},
+                "root": {{ "inputs": {{ "branch": "branch", "tag": "tag" }} }}
+              }},
+              "root": "root",
+              "version": 7
+            }}"#,
+            node("main"),
+            node("refs/tags/v0.1.0")
+        ),
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["outdated", "--json", "flake"])
+        .current_dir(&working_dir)
+        .unwrap();
+
+    io::stdout().write_all(&output.stdout).unwrap();
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert!(output.status.success());
+
+    let rows: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(rows[0]["input"], "branch");
+    assert_eq!(rows[0]["status"], "behind");
+    assert_eq!(rows[0]["latest"], latest_rev.as_str());
+    assert_eq!(rows[1]["input"], "tag");
+    assert_eq!(rows[1]["status"], "up_to_date");
+    assert_eq!(rows[1]["newer_tags"], serde_json::json!(["v0.2.0"]));
+}
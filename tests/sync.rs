@@ -209,3 +209,77 @@ fn test_batch_sync() {
         );
     }
 }
+
+#[sealed_test(files=["tests/fixtures/nested", "tests/fixtures/oneline"])]
+fn test_sync_check() {
+    let working_dir = current_dir().unwrap().to_str().unwrap().to_string();
+    let flake_nix = fs::read_to_string(format!("{working_dir}/oneline/flake.nix")).unwrap();
+
+    let output = Command::cargo_bin("lamina")
+        .unwrap()
+        .args([
+            "batch-sync",
+            "--check",
+            format!("{working_dir}/nested").as_str(),
+            format!("{working_dir}/oneline").as_str(),
+            "nixpkgs-github",
+            "nixpkgs-indirect-rev",
+        ])
+        .output()
+        .unwrap();
+
+    io::stdout().write_all(&output.stdout).unwrap();
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{working_dir}/oneline: nixpkgs-github: dc1517e -> 8b3ad2f (lock only)
+{working_dir}/oneline: nixpkgs-indirect-rev: dc1517e -> 8b3ad2f (flake.nix + lock)
+"
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{working_dir}/oneline/flake.nix")).unwrap(),
+        flake_nix
+    );
+}
+
+#[sealed_test(files=["tests/fixtures/nested", "tests/fixtures/oneline"])]
+fn test_sync_check_from_flake_reference() {
+    let working_dir = current_dir().unwrap().to_str().unwrap().to_string();
+    run_cmd!(
+        git init --quiet nested;
+        git -C nested add .;
+        git -C nested -c user.name=test -c user.email=test@example.com commit --quiet -m "initial";
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("lamina")
+        .unwrap()
+        .args([
+            "batch-sync",
+            "--check",
+            format!("git+file://{working_dir}/nested").as_str(),
+            format!("{working_dir}/oneline").as_str(),
+            "nixpkgs-github",
+            "nixpkgs-indirect-rev",
+        ])
+        .env("XDG_CACHE_HOME", format!("{working_dir}/cache"))
+        .output()
+        .unwrap();
+
+    io::stdout().write_all(&output.stdout).unwrap();
+    io::stderr().write_all(&output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{working_dir}/oneline: nixpkgs-github: dc1517e -> 8b3ad2f (lock only)
+{working_dir}/oneline: nixpkgs-indirect-rev: dc1517e -> 8b3ad2f (flake.nix + lock)
+"
+        )
+    );
+}
@@ -0,0 +1,29 @@
+use std::fs;
+
+use assert_cmd::Command;
+use sealed_test::prelude::*;
+
+#[sealed_test(files=["tests/fixtures/oneline"])]
+fn test_verify_fails_when_flake_nix_changed_without_relocking() {
+    Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["verify", "oneline"])
+        .assert()
+        .success();
+
+    let flake_nix = fs::read_to_string("oneline/flake.nix").unwrap();
+    fs::write(
+        "oneline/flake.nix",
+        flake_nix.replace("\"nixpkgs/release-23.05\"", "\"nixpkgs/release-23.11\""),
+    )
+    .unwrap();
+
+    Command::cargo_bin("lamina")
+        .unwrap()
+        .args(["verify", "oneline"])
+        .assert()
+        .code(4)
+        .stdout(
+            "nixpkgs-indirect-ref: nixpkgs (ref release-23.11) in flake.nix, nixpkgs (ref release-23.05) in flake.lock\n",
+        );
+}